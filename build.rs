@@ -0,0 +1,80 @@
+use std::fmt::Write;
+use std::path::Path;
+
+/// Built-in shaders compiled for the `precompiled-shaders` feature: each GLSL source in
+/// src/graphics/shaders with every set of macros it's compiled with, in the order
+/// `shaders::basic` and `shaders::pick` pass them.
+const SHADERS: &[(&str, &[&[&str]])] = &[
+    ("basic.vert", &[&[]]),
+    (
+        "basic.frag",
+        &[
+            &[],
+            &["SRGB_INPUT"],
+            &["MATERIAL_COLOR"],
+            &["MATERIAL_COLOR", "SRGB_INPUT"],
+        ],
+    ),
+    ("pick.vert", &[&[]]),
+    ("pick.frag", &[&[]]),
+];
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let shader_dir = Path::new("src/graphics/shaders");
+
+    println!("cargo:rerun-if-changed=build.rs");
+    for (source, _) in SHADERS {
+        println!("cargo:rerun-if-changed={}", shader_dir.join(source).display());
+    }
+
+    if std::env::var_os("CARGO_FEATURE_PRECOMPILED_SHADERS").is_none() {
+        return Ok(());
+    }
+
+    let out_dir = std::env::var("OUT_DIR")?;
+    let mut compiler = shaderc::Compiler::new().ok_or("Failed to initialize shaderc")?;
+
+    // Looked up by shaders.rs at runtime, since a shader's defines aren't known until then
+    let mut table = String::from("pub fn spirv(name: &str, defines: &[&str]) -> Option<&'static [u8]> {\n");
+    table.push_str("    match (name, defines) {\n");
+
+    for &(source, variants) in SHADERS {
+        let kind = if source.ends_with(".vert") {
+            shaderc::ShaderKind::Vertex
+        } else {
+            shaderc::ShaderKind::Fragment
+        };
+        let glsl = std::fs::read_to_string(shader_dir.join(source))?;
+
+        for defines in variants {
+            let mut options = shaderc::CompileOptions::new().ok_or("Failed to create shaderc compile options")?;
+            for define in defines.iter() {
+                options.add_macro_definition(define, None);
+            }
+
+            let artifact = compiler.compile_into_spirv(&glsl, kind, source, "main", Some(&options))?;
+            let spirv = Path::new(&out_dir).join(
+                std::iter::once(source)
+                    .chain(defines.iter().copied())
+                    .chain(std::iter::once("spv"))
+                    .collect::<Vec<_>>()
+                    .join("."),
+            );
+            std::fs::write(&spirv, artifact.as_binary_u8())?;
+
+            let pattern = defines.iter().map(|define| format!("{:?}", define)).collect::<Vec<_>>().join(", ");
+            writeln!(
+                table,
+                "        ({:?}, [{}]) => Some(include_bytes!({:?})),",
+                source,
+                pattern,
+                spirv.display().to_string(),
+            )?;
+        }
+    }
+
+    table.push_str("        _ => None,\n    }\n}\n");
+    std::fs::write(Path::new(&out_dir).join("precompiled_shaders.rs"), table)?;
+
+    Ok(())
+}
@@ -1,7 +1,12 @@
+pub mod bounds;
+pub mod frustum;
 pub mod grid;
+pub mod intersection;
 pub mod macros;
+pub mod transform;
 pub mod vector2;
 
+pub use transform::{euler_rotation, Transform};
 pub use vector2::{Vector2, Vector2f};
 
 #[inline]
@@ -24,3 +29,24 @@ pub fn clamp_f32_normalized(value: f32) -> f32 {
 pub fn clamp_f64_normalized(value: f64) -> f64 {
     clamp(value, 0.0, 1.0)
 }
+
+#[inline]
+pub fn approx_eq(a: f64, b: f64, epsilon: f64) -> bool {
+    (a - b).abs() <= epsilon
+}
+
+/// Compare by the number of representable `f64` values between `a` and `b`.
+#[inline]
+pub fn ulps_eq(a: f64, b: f64, max_ulps: u64) -> bool {
+    if a == b {
+        // Also covers +0.0 == -0.0
+        return true;
+    }
+
+    if a.is_nan() || b.is_nan() || a.is_sign_positive() != b.is_sign_positive() {
+        return false;
+    }
+
+    let distance = (a.to_bits() as i64).wrapping_sub(b.to_bits() as i64).unsigned_abs();
+    distance <= max_ulps
+}
@@ -0,0 +1,144 @@
+use cgmath::{EuclideanSpace, InnerSpace, Matrix4, Point3};
+
+/// Axis-aligned bounding box.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aabb {
+    pub min: Point3<f32>,
+    pub max: Point3<f32>,
+}
+
+impl Aabb {
+    pub fn new(min: Point3<f32>, max: Point3<f32>) -> Self {
+        Self { min, max }
+    }
+
+    /// The smallest box containing every point, or `None` if there are no points.
+    pub fn from_points(points: impl IntoIterator<Item = Point3<f32>>) -> Option<Self> {
+        let mut points = points.into_iter();
+        let first = points.next()?;
+
+        Some(points.fold(Self::new(first, first), |aabb, point| aabb.including(point)))
+    }
+
+    /// The smallest box containing both this box and `point`.
+    pub fn including(&self, point: Point3<f32>) -> Self {
+        Self {
+            min: Point3::new(self.min.x.min(point.x), self.min.y.min(point.y), self.min.z.min(point.z)),
+            max: Point3::new(self.max.x.max(point.x), self.max.y.max(point.y), self.max.z.max(point.z)),
+        }
+    }
+
+    /// The smallest box containing both boxes.
+    pub fn union(&self, other: &Aabb) -> Self {
+        self.including(other.min).including(other.max)
+    }
+
+    pub fn center(&self) -> Point3<f32> {
+        self.min.midpoint(self.max)
+    }
+
+    pub fn corners(&self) -> [Point3<f32>; 8] {
+        let (min, max) = (self.min, self.max);
+        [
+            Point3::new(min.x, min.y, min.z),
+            Point3::new(max.x, min.y, min.z),
+            Point3::new(min.x, max.y, min.z),
+            Point3::new(max.x, max.y, min.z),
+            Point3::new(min.x, min.y, max.z),
+            Point3::new(max.x, min.y, max.z),
+            Point3::new(min.x, max.y, max.z),
+            Point3::new(max.x, max.y, max.z),
+        ]
+    }
+
+    /// The smallest axis-aligned box containing this box after transforming it by `matrix`.
+    pub fn transformed(&self, matrix: &Matrix4<f32>) -> Self {
+        use cgmath::Transform;
+
+        let corners = self.corners();
+        Self::from_points(corners.iter().map(|&corner| matrix.transform_point(corner))).unwrap()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoundingSphere {
+    pub center: Point3<f32>,
+    pub radius: f32,
+}
+
+impl BoundingSphere {
+    pub fn new(center: Point3<f32>, radius: f32) -> Self {
+        Self { center, radius }
+    }
+
+    /// A sphere centered on the points' bounding box that contains every point.
+    ///
+    /// Not the minimal bounding sphere, but cheap and close enough for culling and picking.
+    pub fn from_points(points: impl IntoIterator<Item = Point3<f32>> + Clone) -> Option<Self> {
+        let center = Aabb::from_points(points.clone())?.center();
+        let radius = points
+            .into_iter()
+            .map(|point| (point - center).magnitude())
+            .fold(0.0, f32::max);
+
+        Some(Self::new(center, radius))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cgmath::{Deg, Vector3};
+
+    const EPSILON: f32 = 1e-5;
+
+    fn assert_points_eq(actual: Point3<f32>, expected: Point3<f32>) {
+        assert!((actual - expected).magnitude() < EPSILON, "{:?} != {:?}", actual, expected);
+    }
+
+    #[test]
+    fn from_points_spans_every_point() {
+        let aabb = Aabb::from_points(vec![
+            Point3::new(1.0, -2.0, 3.0),
+            Point3::new(-1.0, 4.0, 0.0),
+            Point3::new(0.0, 0.0, 5.0),
+        ])
+        .unwrap();
+
+        assert_eq!(aabb.min, Point3::new(-1.0, -2.0, 0.0));
+        assert_eq!(aabb.max, Point3::new(1.0, 4.0, 5.0));
+        assert_eq!(aabb.center(), Point3::new(0.0, 1.0, 2.5));
+        assert_eq!(Aabb::from_points(Vec::new()), None);
+    }
+
+    #[test]
+    fn union_contains_both_boxes() {
+        let a = Aabb::new(Point3::new(0.0, 0.0, 0.0), Point3::new(1.0, 1.0, 1.0));
+        let b = Aabb::new(Point3::new(2.0, -1.0, 0.5), Point3::new(3.0, 0.5, 0.75));
+
+        let union = a.union(&b);
+        assert_eq!(union.min, Point3::new(0.0, -1.0, 0.0));
+        assert_eq!(union.max, Point3::new(3.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn transformed_bounds_the_rotated_corners() {
+        let aabb = Aabb::new(Point3::new(0.0, 0.0, 0.0), Point3::new(2.0, 1.0, 1.0));
+        let matrix = Matrix4::from_translation(Vector3::new(10.0, 0.0, 0.0)) * Matrix4::from_angle_z(Deg(90.0));
+
+        let transformed = aabb.transformed(&matrix);
+        assert_points_eq(transformed.min, Point3::new(9.0, 0.0, 0.0));
+        assert_points_eq(transformed.max, Point3::new(10.0, 2.0, 1.0));
+    }
+
+    #[test]
+    fn bounding_sphere_contains_every_point() {
+        let points = vec![Point3::new(-3.0, 0.0, 0.0), Point3::new(3.0, 0.0, 0.0), Point3::new(0.0, 4.0, 0.0)];
+        let sphere = BoundingSphere::from_points(points.clone()).unwrap();
+
+        assert_points_eq(sphere.center, Point3::new(0.0, 2.0, 0.0));
+        assert!((sphere.radius - 13f32.sqrt()).abs() < EPSILON, "radius {}", sphere.radius);
+        assert!(points.iter().all(|&point| (point - sphere.center).magnitude() <= sphere.radius + EPSILON));
+        assert_eq!(BoundingSphere::from_points(Vec::new()), None);
+    }
+}
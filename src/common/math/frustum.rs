@@ -0,0 +1,98 @@
+use cgmath::{InnerSpace, Matrix, Matrix4, Point3, Vector4};
+
+/// The six planes bounding a camera's view volume, pointing inwards.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Frustum {
+    planes: [Vector4<f32>; 6],
+}
+
+impl Frustum {
+    /// Extract the planes from a view projection matrix with wgpu's 0..1 depth range.
+    pub fn from_matrix(view_proj: &Matrix4<f32>) -> Self {
+        let x = view_proj.row(0);
+        let y = view_proj.row(1);
+        let z = view_proj.row(2);
+        let w = view_proj.row(3);
+
+        Self {
+            planes: [
+                normalize_plane(w + x), // left
+                normalize_plane(w - x), // right
+                normalize_plane(w + y), // bottom
+                normalize_plane(w - y), // top
+                normalize_plane(z),     // near
+                normalize_plane(w - z), // far
+            ],
+        }
+    }
+
+    /// `false` only when the sphere is entirely outside the frustum.
+    pub fn intersects_sphere(&self, center: Point3<f32>, radius: f32) -> bool {
+        self.planes.iter().all(|plane| {
+            plane.x * center.x + plane.y * center.y + plane.z * center.z + plane.w >= -radius
+        })
+    }
+}
+
+/// Scale a plane so its normal is unit length, making its distances true distances.
+fn normalize_plane(plane: Vector4<f32>) -> Vector4<f32> {
+    plane / plane.truncate().magnitude()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graphics::camera::OPENGL_TO_WGPU_MATRIX;
+    use cgmath::{Deg, Vector3};
+
+    /// A camera at the origin looking down -Z, with planes at 1 and 100.
+    fn frustum() -> Frustum {
+        let view = Matrix4::look_at(Point3::new(0.0, 0.0, 0.0), Point3::new(0.0, 0.0, -1.0), Vector3::unit_y());
+        let proj = OPENGL_TO_WGPU_MATRIX * cgmath::perspective(Deg(90.0), 1.0, 1.0, 100.0);
+        Frustum::from_matrix(&(proj * view))
+    }
+
+    fn distance(plane: Vector4<f32>, point: Point3<f32>) -> f32 {
+        plane.x * point.x + plane.y * point.y + plane.z * point.z + plane.w
+    }
+
+    #[test]
+    fn planes_point_inwards() {
+        let frustum = frustum();
+        let inside = Point3::new(0.0, 0.0, -10.0);
+
+        for (i, plane) in frustum.planes.iter().enumerate() {
+            assert!((plane.truncate().magnitude() - 1.0).abs() < 1e-5, "plane {} isn't normalized", i);
+            assert!(distance(*plane, inside) > 0.0, "plane {} points outwards: {:?}", i, plane);
+        }
+    }
+
+    #[test]
+    fn plane_distances_are_true_distances() {
+        let [_, _, _, _, near, far] = frustum().planes;
+
+        assert!((distance(near, Point3::new(0.0, 0.0, -3.0)) - 2.0).abs() < 1e-3);
+        assert!((distance(far, Point3::new(0.0, 0.0, -90.0)) - 10.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn spheres_outside_a_plane_are_culled() {
+        let frustum = frustum();
+
+        assert!(frustum.intersects_sphere(Point3::new(0.0, 0.0, -10.0), 1.0));
+        // Behind the camera, past the near plane, beyond the far plane, off to each side
+        assert!(!frustum.intersects_sphere(Point3::new(0.0, 0.0, 5.0), 1.0));
+        assert!(!frustum.intersects_sphere(Point3::new(0.0, 0.0, -0.5), 0.25));
+        assert!(!frustum.intersects_sphere(Point3::new(0.0, 0.0, -110.0), 1.0));
+        assert!(!frustum.intersects_sphere(Point3::new(-20.0, 0.0, -10.0), 1.0));
+        assert!(!frustum.intersects_sphere(Point3::new(20.0, 0.0, -10.0), 1.0));
+        assert!(!frustum.intersects_sphere(Point3::new(0.0, -20.0, -10.0), 1.0));
+        assert!(!frustum.intersects_sphere(Point3::new(0.0, 20.0, -10.0), 1.0));
+    }
+
+    #[test]
+    fn spheres_straddling_a_plane_are_kept() {
+        // Centered just outside the right plane (x = -z at this depth), but reaching back in
+        assert!(frustum().intersects_sphere(Point3::new(11.0, 0.0, -10.0), 1.0));
+    }
+}
@@ -0,0 +1,72 @@
+use cgmath::{InnerSpace, Point3, Vector3};
+
+/// Distance along a ray to the nearest point where it enters a sphere.
+///
+/// `direction` must be normalized. A ray starting inside the sphere hits the far side;
+/// spheres entirely behind the origin aren't hit.
+pub fn ray_sphere(
+    origin: Point3<f32>,
+    direction: Vector3<f32>,
+    center: Point3<f32>,
+    radius: f32,
+) -> Option<f32> {
+    let offset = origin - center;
+    let b = offset.dot(direction);
+    let c = offset.magnitude2() - radius * radius;
+
+    let discriminant = b * b - c;
+    if discriminant < 0.0 {
+        return None;
+    }
+
+    let root = discriminant.sqrt();
+    let near = -b - root;
+    let far = -b + root;
+
+    if near >= 0.0 {
+        Some(near)
+    } else if far >= 0.0 {
+        Some(far)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPSILON: f32 = 1e-5;
+
+    fn forward() -> Vector3<f32> {
+        Vector3::new(0.0, 0.0, -1.0)
+    }
+
+    #[test]
+    fn hits_the_near_side() {
+        let distance = ray_sphere(Point3::new(0.0, 0.0, 0.0), forward(), Point3::new(0.0, 0.0, -10.0), 2.0);
+        assert!((distance.unwrap() - 8.0).abs() < EPSILON, "{:?}", distance);
+    }
+
+    #[test]
+    fn grazing_ray_hits_once() {
+        let distance = ray_sphere(Point3::new(2.0, 0.0, 0.0), forward(), Point3::new(0.0, 0.0, -10.0), 2.0);
+        assert!((distance.unwrap() - 10.0).abs() < EPSILON, "{:?}", distance);
+    }
+
+    #[test]
+    fn misses_a_sphere_off_to_the_side() {
+        assert_eq!(ray_sphere(Point3::new(3.0, 0.0, 0.0), forward(), Point3::new(0.0, 0.0, -10.0), 2.0), None);
+    }
+
+    #[test]
+    fn misses_a_sphere_behind_the_origin() {
+        assert_eq!(ray_sphere(Point3::new(0.0, 0.0, 0.0), forward(), Point3::new(0.0, 0.0, 10.0), 2.0), None);
+    }
+
+    #[test]
+    fn from_inside_hits_the_far_side() {
+        let distance = ray_sphere(Point3::new(0.0, 0.0, -9.0), forward(), Point3::new(0.0, 0.0, -10.0), 2.0);
+        assert!((distance.unwrap() - 3.0).abs() < EPSILON, "{:?}", distance);
+    }
+}
@@ -1,6 +1,8 @@
 #[macro_export]
 macro_rules! vector2_impl {
     ($impl_type:ty) => {
+        impl Copy for Vector2<$impl_type> {}
+
         impl std::ops::Add<&Vector2<$impl_type>> for &Vector2<$impl_type> {
             type Output = Vector2<$impl_type>;
 
@@ -155,8 +157,48 @@ macro_rules! vector2_impl {
 
         impl Vector2<$impl_type> {
             pub fn magnitude(&self) -> f64 {
-                let sum_squares = (self.x * self.x) + (self.y * self.y);
-                (sum_squares as f64).sqrt()
+                (self.magnitude_squared() as f64).sqrt()
+            }
+
+            /// Squared length, cheaper than `magnitude` when only comparing lengths.
+            pub fn magnitude_squared(&self) -> $impl_type {
+                (self.x * self.x) + (self.y * self.y)
+            }
+
+            pub fn dot(&self, other: &Vector2<$impl_type>) -> $impl_type {
+                (self.x * other.x) + (self.y * other.y)
+            }
+
+            /// Component-wise minimum.
+            pub fn min(&self, other: &Vector2<$impl_type>) -> Vector2<$impl_type> {
+                Vector2 {
+                    x: self.x.min(other.x),
+                    y: self.y.min(other.y),
+                }
+            }
+
+            /// Component-wise maximum.
+            pub fn max(&self, other: &Vector2<$impl_type>) -> Vector2<$impl_type> {
+                Vector2 {
+                    x: self.x.max(other.x),
+                    y: self.y.max(other.y),
+                }
+            }
+
+            /// Component-wise clamp into the box from `min` to `max`, with `math::clamp`.
+            pub fn clamp(&self, min: &Vector2<$impl_type>, max: &Vector2<$impl_type>) -> Vector2<$impl_type> {
+                Vector2 {
+                    x: $crate::common::math::clamp(self.x, min.x, max.x),
+                    y: $crate::common::math::clamp(self.y, min.y, max.y),
+                }
+            }
+
+            /// Component-wise absolute value.
+            pub fn abs(&self) -> Vector2<$impl_type> {
+                Vector2 {
+                    x: self.x.abs(),
+                    y: self.y.abs(),
+                }
             }
 
             pub fn distance(&self, other: &Vector2<$impl_type>) -> f64 {
@@ -167,6 +209,10 @@ macro_rules! vector2_impl {
                 self - target
             }
 
+            /// Unit vector in the same direction.
+            ///
+            /// This is the unchecked fast path: the zero vector produces NaN components.
+            /// Use `try_normalized` when the vector may be zero.
             pub fn normalized(&self) -> Vector2<f64> {
                 let magnitude = self.magnitude();
                 Vector2 {
@@ -174,6 +220,31 @@ macro_rules! vector2_impl {
                     y: self.y as f64 / magnitude,
                 }
             }
+
+            /// Unit vector in the same direction, or `None` for a (near) zero vector.
+            pub fn try_normalized(&self) -> Option<Vector2<f64>> {
+                let magnitude = self.magnitude();
+                if magnitude <= f64::EPSILON {
+                    None
+                } else {
+                    Some(Vector2 {
+                        x: self.x as f64 / magnitude,
+                        y: self.y as f64 / magnitude,
+                    })
+                }
+            }
+        }
+
+        /// `(x, y)`, with the formatter's width and precision applied to each component,
+        /// so `{:.2}` prints `(1.00, 2.00)`.
+        impl std::fmt::Display for Vector2<$impl_type> {
+            fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("(")?;
+                std::fmt::Display::fmt(&self.x, f)?;
+                f.write_str(", ")?;
+                std::fmt::Display::fmt(&self.y, f)?;
+                f.write_str(")")
+            }
         }
     };
 }
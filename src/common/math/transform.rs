@@ -0,0 +1,153 @@
+use cgmath::{ElementWise, Matrix4, Quaternion, Rad, Rotation3, Vector3};
+
+/// A translation, rotation and scale, applied to a point in scale, rotate, translate order.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transform {
+    pub translation: Vector3<f32>,
+    pub rotation: Quaternion<f32>,
+    pub scale: Vector3<f32>,
+}
+
+impl Transform {
+    pub const IDENTITY: Transform = Transform {
+        translation: Vector3 { x: 0.0, y: 0.0, z: 0.0 },
+        rotation: Quaternion {
+            s: 1.0,
+            v: Vector3 { x: 0.0, y: 0.0, z: 0.0 },
+        },
+        scale: Vector3 { x: 1.0, y: 1.0, z: 1.0 },
+    };
+
+    pub fn new(translation: Vector3<f32>, rotation: Quaternion<f32>, scale: Vector3<f32>) -> Self {
+        Self {
+            translation,
+            rotation,
+            scale,
+        }
+    }
+
+    pub fn from_translation(translation: Vector3<f32>) -> Self {
+        Self {
+            translation,
+            ..Self::IDENTITY
+        }
+    }
+
+    pub fn from_rotation(rotation: Quaternion<f32>) -> Self {
+        Self {
+            rotation,
+            ..Self::IDENTITY
+        }
+    }
+
+    pub fn from_scale(scale: Vector3<f32>) -> Self {
+        Self {
+            scale,
+            ..Self::IDENTITY
+        }
+    }
+
+    pub fn matrix(&self) -> Matrix4<f32> {
+        Matrix4::from_translation(self.translation)
+            * Matrix4::from(self.rotation)
+            * Matrix4::from_nonuniform_scale(self.scale.x, self.scale.y, self.scale.z)
+    }
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+/// A rotation from yaw, pitch and roll angles, applied in YXZ order: roll about Z first,
+/// then pitch about X, then yaw about Y. Each turns counter-clockwise seen from the positive
+/// end of its axis, so a positive yaw turns +Z toward +X and a positive pitch turns +Z toward -Y.
+pub fn euler_rotation(yaw: impl Into<Rad<f32>>, pitch: impl Into<Rad<f32>>, roll: impl Into<Rad<f32>>) -> Quaternion<f32> {
+    Quaternion::from_angle_y(yaw) * Quaternion::from_angle_x(pitch) * Quaternion::from_angle_z(roll)
+}
+
+/// Compose two transforms so that `(parent * child)` applies `child` first.
+///
+/// Scales are multiplied component-wise, which is exact as long as the parent's
+/// scale is uniform; a non-uniform parent scale combined with a rotated child
+/// would introduce shear, which a `Transform` can't represent.
+impl std::ops::Mul<Transform> for Transform {
+    type Output = Transform;
+
+    fn mul(self, rhs: Transform) -> Self::Output {
+        Transform {
+            translation: self.translation
+                + self.rotation * self.scale.mul_element_wise(rhs.translation),
+            rotation: self.rotation * rhs.rotation,
+            scale: self.scale.mul_element_wise(rhs.scale),
+        }
+    }
+}
+
+impl std::ops::MulAssign<Transform> for Transform {
+    fn mul_assign(&mut self, rhs: Transform) {
+        *self = *self * rhs;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cgmath::{Deg, InnerSpace, Point3, SquareMatrix, Transform as _};
+
+    const EPSILON: f32 = 1e-5;
+
+    fn assert_points_eq(actual: Point3<f32>, expected: Point3<f32>) {
+        assert!((actual - expected).magnitude() < EPSILON, "{:?} != {:?}", actual, expected);
+    }
+
+    #[test]
+    fn identity_matrix() {
+        assert_eq!(Transform::IDENTITY.matrix(), Matrix4::identity());
+        assert_eq!(Transform::default(), Transform::IDENTITY);
+    }
+
+    #[test]
+    fn matrix_scales_then_rotates_then_translates() {
+        let transform = Transform::new(
+            Vector3::new(10.0, 0.0, 0.0),
+            Quaternion::from_angle_z(Deg(90.0)),
+            Vector3::new(2.0, 1.0, 1.0),
+        );
+
+        // (1, 0, 0) scales to (2, 0, 0), rotates to (0, 2, 0), then moves to (10, 2, 0)
+        assert_points_eq(transform.matrix().transform_point(Point3::new(1.0, 0.0, 0.0)), Point3::new(10.0, 2.0, 0.0));
+    }
+
+    #[test]
+    fn euler_rotation_directions() {
+        let forward = Vector3::new(0.0, 0.0, 1.0);
+
+        let yawed = euler_rotation(Deg(90.0), Deg(0.0), Deg(0.0)) * forward;
+        assert!((yawed - Vector3::new(1.0, 0.0, 0.0)).magnitude() < EPSILON, "{:?}", yawed);
+
+        let pitched = euler_rotation(Deg(0.0), Deg(90.0), Deg(0.0)) * forward;
+        assert!((pitched - Vector3::new(0.0, -1.0, 0.0)).magnitude() < EPSILON, "{:?}", pitched);
+    }
+
+    #[test]
+    fn composition_applies_the_child_first() {
+        let parent = Transform::new(
+            Vector3::new(0.0, 5.0, 0.0),
+            Quaternion::from_angle_y(Deg(90.0)),
+            Vector3::new(2.0, 2.0, 2.0),
+        );
+        let child = Transform::from_translation(Vector3::new(0.0, 0.0, 1.0));
+        let point = Point3::new(1.0, 0.0, 0.0);
+
+        let composed = (parent * child).matrix().transform_point(point);
+        let nested = parent.matrix().transform_point(child.matrix().transform_point(point));
+        assert_points_eq(composed, nested);
+        assert_points_eq(composed, Point3::new(2.0, 5.0, -2.0));
+
+        let mut assigned = parent;
+        assigned *= child;
+        assert_eq!(assigned, parent * child);
+    }
+}
@@ -1,9 +1,10 @@
-use crate::vector2_impl;
+use crate::{common::math, vector2_impl};
 
 pub type Vector2f = Vector2<f64>;
 pub type Vector2i = Vector2<i32>;
 
-#[derive(Debug, Clone)]
+// Eq and Hash only apply when `T` has them, so they cover Vector2i but not Vector2f
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Vector2<T> {
     pub x: T,
     pub y: T,
@@ -13,6 +14,22 @@ impl<T> Vector2<T> {
     pub fn new(x: T, y: T) -> Self {
         Self { x, y }
     }
+
+    /// Apply `f` to each component, e.g. `v.map(|c| c as i32)`.
+    pub fn map<U>(self, f: impl Fn(T) -> U) -> Vector2<U> {
+        Vector2 {
+            x: f(self.x),
+            y: f(self.y),
+        }
+    }
+
+    /// Combine the matching components of two vectors with `f`.
+    pub fn zip_with<U, V>(self, other: Vector2<U>, f: impl Fn(T, U) -> V) -> Vector2<V> {
+        Vector2 {
+            x: f(self.x, other.x),
+            y: f(self.y, other.y),
+        }
+    }
 }
 
 vector2_impl!(f64);
@@ -39,10 +56,279 @@ impl<T> From<Vector2<T>> for (T, T) {
     }
 }
 
+impl<T> From<[T; 2]> for Vector2<T> {
+    fn from([x, y]: [T; 2]) -> Self {
+        Self { x, y }
+    }
+}
+
+impl<T> From<Vector2<T>> for [T; 2] {
+    fn from(vector: Vector2<T>) -> Self {
+        [vector.x, vector.y]
+    }
+}
+
+impl From<Vector2i> for Vector2f {
+    fn from(vector: Vector2i) -> Self {
+        Self {
+            x: vector.x.into(),
+            y: vector.y.into(),
+        }
+    }
+}
+
+impl<T: Copy> Vector2<T> {
+    pub fn as_array(&self) -> [T; 2] {
+        [self.x, self.y]
+    }
+
+    /// The vector with its components swapped.
+    pub fn yx(&self) -> Vector2<T> {
+        Vector2 {
+            x: self.y,
+            y: self.x,
+        }
+    }
+}
+
+// Conversions to and from cgmath's types, so code that only uses `Vector2` doesn't see cgmath
+#[cfg(feature = "cgmath-interop")]
+impl Vector2<f64> {
+    /// `(x, y, 0)`, on the XY plane facing the default camera.
+    pub fn xy0(&self) -> cgmath::Vector3<f32> {
+        cgmath::Vector3::new(self.x as f32, self.y as f32, 0.0)
+    }
+
+    /// `(x, 0, y)`, on the XZ ground plane.
+    pub fn x0y(&self) -> cgmath::Vector3<f32> {
+        cgmath::Vector3::new(self.x as f32, 0.0, self.y as f32)
+    }
+}
+
+#[cfg(feature = "cgmath-interop")]
+impl<T> From<cgmath::Vector2<T>> for Vector2<T> {
+    fn from(v: cgmath::Vector2<T>) -> Self {
+        Self::new(v.x, v.y)
+    }
+}
+
+#[cfg(feature = "cgmath-interop")]
+impl<T> From<Vector2<T>> for cgmath::Vector2<T> {
+    fn from(v: Vector2<T>) -> Self {
+        cgmath::Vector2::new(v.x, v.y)
+    }
+}
+
 impl Vector2<f64> {
     pub const ZERO: Vector2<f64> = Vector2 { x: 0.0, y: 0.0 };
 
     pub fn with_magnitude(&self, magnitude: f64) -> Vector2f {
         magnitude * self.normalized()
     }
+
+    pub fn floor(&self) -> Vector2f {
+        Vector2 {
+            x: self.x.floor(),
+            y: self.y.floor(),
+        }
+    }
+
+    pub fn ceil(&self) -> Vector2f {
+        Vector2 {
+            x: self.x.ceil(),
+            y: self.y.ceil(),
+        }
+    }
+
+    /// Round each component to the nearest integer, halfway cases away from zero.
+    pub fn round(&self) -> Vector2f {
+        Vector2 {
+            x: self.x.round(),
+            y: self.y.round(),
+        }
+    }
+
+    /// Round down to the integer grid. Components outside the `i32` range saturate.
+    pub fn to_i32_floor(&self) -> Vector2i {
+        Vector2 {
+            x: self.x.floor() as i32,
+            y: self.y.floor() as i32,
+        }
+    }
+
+    /// Round to the nearest integer, halfway cases away from zero. Components outside the
+    /// `i32` range saturate.
+    pub fn to_i32_round(&self) -> Vector2i {
+        Vector2 {
+            x: self.x.round() as i32,
+            y: self.y.round() as i32,
+        }
+    }
+
+    /// Round up to the integer grid. Components outside the `i32` range saturate.
+    pub fn to_i32_ceil(&self) -> Vector2i {
+        Vector2 {
+            x: self.x.ceil() as i32,
+            y: self.y.ceil() as i32,
+        }
+    }
+
+    /// Component-wise comparison with an absolute tolerance.
+    pub fn approx_eq(&self, other: &Self, epsilon: f64) -> bool {
+        math::approx_eq(self.x, other.x, epsilon) && math::approx_eq(self.y, other.y, epsilon)
+    }
+
+    /// Component-wise comparison allowing up to `max_ulps` representable values of difference.
+    pub fn ulps_eq(&self, other: &Self, max_ulps: u64) -> bool {
+        math::ulps_eq(self.x, other.x, max_ulps) && math::ulps_eq(self.y, other.y, max_ulps)
+    }
+
+    /// Scale down to at most `max` length, keeping the direction. Shorter vectors,
+    /// including the zero vector, are returned unchanged.
+    pub fn clamped_magnitude(&self, max: f64) -> Vector2f {
+        let magnitude = self.magnitude();
+        if magnitude > max {
+            self * (max / magnitude)
+        } else {
+            *self
+        }
+    }
+
+    /// Length of the component of `self` along `other`. Zero if `other` is the zero vector.
+    pub fn scalar_projection(&self, other: &Vector2f) -> f64 {
+        let magnitude = other.magnitude();
+        if magnitude == 0.0 {
+            0.0
+        } else {
+            self.dot(other) / magnitude
+        }
+    }
+
+    /// The component of `self` along `other`. Zero if `other` is the zero vector.
+    pub fn project_onto(&self, other: &Vector2f) -> Vector2f {
+        let magnitude_squared = other.dot(other);
+        if magnitude_squared == 0.0 {
+            Vector2f::ZERO
+        } else {
+            other * (self.dot(other) / magnitude_squared)
+        }
+    }
+
+    /// Rotate counter-clockwise by `radians`.
+    pub fn rotated(&self, radians: f64) -> Vector2f {
+        let (sin, cos) = radians.sin_cos();
+        Vector2 {
+            x: self.x * cos - self.y * sin,
+            y: self.x * sin + self.y * cos,
+        }
+    }
+
+    /// Rotate counter-clockwise by 90 degrees.
+    pub fn perpendicular(&self) -> Vector2f {
+        Vector2 {
+            x: -self.y,
+            y: self.x,
+        }
+    }
+
+    /// Reflect off a surface with the given `normal`, which must already be normalized.
+    pub fn reflected(&self, normal: &Vector2f) -> Vector2f {
+        self - 2.0 * self.dot(normal) * normal
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::{FRAC_PI_2, PI};
+
+    const EPSILON: f64 = 1e-12;
+
+    fn assert_approx_eq(actual: Vector2f, expected: Vector2f) {
+        assert!(actual.approx_eq(&expected, EPSILON), "{:?} != {:?}", actual, expected);
+    }
+
+    #[test]
+    fn map_and_zip_with_apply_per_component() {
+        assert_eq!(Vector2f::new(1.5, -2.5).map(|c| c as i32), Vector2i::new(1, -2));
+        assert_eq!(Vector2i::new(1, 2).zip_with(Vector2i::new(10, 20), |a, b| a * b), Vector2i::new(10, 40));
+        assert_eq!(Vector2i::new(1, 2).yx(), Vector2i::new(2, 1));
+    }
+
+    #[test]
+    fn rotated_turns_counter_clockwise() {
+        assert_approx_eq(Vector2f::new(1.0, 0.0).rotated(FRAC_PI_2), Vector2f::new(0.0, 1.0));
+        assert_approx_eq(Vector2f::new(0.0, 1.0).rotated(FRAC_PI_2), Vector2f::new(-1.0, 0.0));
+        assert_approx_eq(Vector2f::new(3.0, 4.0).rotated(PI), Vector2f::new(-3.0, -4.0));
+        assert_approx_eq(Vector2f::new(3.0, 4.0).rotated(-FRAC_PI_2), Vector2f::new(4.0, -3.0));
+    }
+
+    #[test]
+    fn perpendicular_matches_a_quarter_turn() {
+        let vector = Vector2f::new(3.0, 4.0);
+        assert_eq!(vector.perpendicular(), Vector2f::new(-4.0, 3.0));
+        assert_approx_eq(vector.perpendicular(), vector.rotated(FRAC_PI_2));
+        assert_eq!(vector.dot(&vector.perpendicular()), 0.0);
+    }
+
+    #[test]
+    fn reflected_flips_the_component_along_the_normal() {
+        let up = Vector2f::new(0.0, 1.0);
+        assert_approx_eq(Vector2f::new(1.0, -1.0).reflected(&up), Vector2f::new(1.0, 1.0));
+        assert_approx_eq(Vector2f::new(2.0, 0.0).reflected(&up), Vector2f::new(2.0, 0.0));
+
+        let diagonal = Vector2f::new(1.0, 1.0).normalized();
+        assert_approx_eq(Vector2f::new(-1.0, 0.0).reflected(&diagonal), Vector2f::new(0.0, 1.0));
+    }
+
+    #[test]
+    fn project_onto_keeps_the_parallel_component() {
+        let vector = Vector2f::new(3.0, 4.0);
+        assert_approx_eq(vector.project_onto(&Vector2f::new(2.0, 0.0)), Vector2f::new(3.0, 0.0));
+        assert_approx_eq(vector.project_onto(&Vector2f::new(0.0, -5.0)), Vector2f::new(0.0, 4.0));
+        assert_eq!(vector.project_onto(&Vector2f::ZERO), Vector2f::ZERO);
+    }
+
+    #[test]
+    fn scalar_projection_is_signed_length_along_other() {
+        let vector = Vector2f::new(3.0, 4.0);
+        assert_eq!(vector.scalar_projection(&Vector2f::new(10.0, 0.0)), 3.0);
+        assert_eq!(vector.scalar_projection(&Vector2f::new(0.0, -2.0)), -4.0);
+        assert_eq!(vector.scalar_projection(&Vector2f::ZERO), 0.0);
+    }
+
+    #[test]
+    fn clamped_magnitude_only_shortens() {
+        assert_approx_eq(Vector2f::new(3.0, 4.0).clamped_magnitude(1.0), Vector2f::new(0.6, 0.8));
+        assert_eq!(Vector2f::new(3.0, 4.0).clamped_magnitude(10.0), Vector2f::new(3.0, 4.0));
+        assert_eq!(Vector2f::ZERO.clamped_magnitude(1.0), Vector2f::ZERO);
+    }
+
+    #[test]
+    fn rounding() {
+        let vector = Vector2f::new(1.5, -1.5);
+        assert_eq!(vector.floor(), Vector2f::new(1.0, -2.0));
+        assert_eq!(vector.ceil(), Vector2f::new(2.0, -1.0));
+        assert_eq!(vector.round(), Vector2f::new(2.0, -2.0));
+        assert_eq!(vector.to_i32_floor(), Vector2i::new(1, -2));
+        assert_eq!(vector.to_i32_ceil(), Vector2i::new(2, -1));
+        assert_eq!(vector.to_i32_round(), Vector2i::new(2, -2));
+        assert_eq!(Vector2f::new(1e20, -1e20).to_i32_floor(), Vector2i::new(i32::MAX, i32::MIN));
+    }
+
+    #[test]
+    fn approx_eq_uses_an_absolute_tolerance() {
+        let vector = Vector2f::new(1.0, 2.0);
+        assert!(vector.approx_eq(&Vector2f::new(1.05, 1.95), 0.1));
+        assert!(!vector.approx_eq(&Vector2f::new(1.0, 2.2), 0.1));
+    }
+
+    #[test]
+    fn ulps_eq_counts_representable_values() {
+        let vector = Vector2f::new(0.1 + 0.2, 1.0);
+        assert_ne!(vector, Vector2f::new(0.3, 1.0));
+        assert!(vector.ulps_eq(&Vector2f::new(0.3, 1.0), 1));
+        assert!(!Vector2f::new(1.0, 1.0).ulps_eq(&Vector2f::new(1.0 + 1e-10, 1.0), 4));
+        assert!(Vector2f::new(0.0, -0.0).ulps_eq(&Vector2f::new(-0.0, 0.0), 0));
+    }
 }
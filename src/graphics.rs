@@ -1,19 +1,38 @@
 pub mod camera;
 pub mod color;
 pub mod config;
+pub mod frame_stats;
 pub mod instance;
+pub mod key_bindings;
+pub mod layer;
+pub mod light;
+#[cfg(feature = "tobj")]
+pub mod obj;
 pub mod object;
+pub mod pipeline;
+pub mod primitives;
+pub mod render_context;
+pub mod run;
+pub mod scene;
 pub mod shaders;
 pub mod shape;
 pub mod state;
 pub mod uniforms;
 pub mod vertex;
 
-pub use camera::{Camera, CameraController};
+pub use camera::{Camera, CameraController, FovAxis, MovementMode, OverlayCamera, SpeedModifiers};
 pub use color::Color;
 pub use config::GraphicsConfig;
+pub use frame_stats::{BenchResult, FrameStats};
 pub use instance::{Instance, InstanceRaw};
+pub use key_bindings::{CameraAction, KeyBindings};
+pub use layer::Layer;
+pub use light::{Attenuation, Light, LightUniforms, MAX_LIGHTS};
 pub use object::Object;
+pub use pipeline::{DepthBias, MaterialId, PipelineConfig};
+pub use render_context::{RenderCallback, RenderContext};
+pub use run::run;
+pub use scene::{InstanceData, ObjectData, SceneData};
 pub use shaders::ShaderCompiler;
 pub use state::State;
 pub use uniforms::Uniforms;
@@ -1,21 +1,231 @@
-use winit::event::{ElementState, KeyboardInput, VirtualKeyCode, WindowEvent};
+use std::time::{Duration, Instant};
 
+use winit::dpi::PhysicalPosition;
+use winit::error::ExternalError;
+use winit::event::{ElementState, KeyboardInput, MouseScrollDelta, WindowEvent};
+use winit::window::Window;
+
+use crate::common::math::Vector2f;
+use crate::graphics::{CameraAction, KeyBindings};
+
+/// A perspective camera.
+///
+/// World space is right handed with +Y up; the camera looks from `eye` toward `target`,
+/// which starts out straight down -Z.
+/// `fovy` is the field of view in degrees, vertical unless `fov_axis` says otherwise.
 pub struct Camera {
     pub eye: cgmath::Point3<f32>,
+    pub target: cgmath::Point3<f32>,
     pub up: cgmath::Vector3<f32>,
     pub aspect: f32,
+    /// Field of view in degrees along `fov_axis`, vertical by default. Use
+    /// `set_fovy_radians` to set it from radians.
     pub fovy: f32,
+    /// Which field of view stays fixed when `aspect` changes.
+    pub fov_axis: FovAxis,
     pub znear: f32,
     pub zfar: f32,
+    /// Distance along the view direction that is in perfect focus. Not used by the
+    /// projection; it's carried for depth of field effects.
+    pub focus_distance: f32,
+    /// Lens aperture for depth of field effects; `0.0` is a pinhole camera with no blur.
+    pub aperture: f32,
 }
 
+pub const DEFAULT_FOCUS_DISTANCE: f32 = 10.0;
+pub const DEFAULT_APERTURE: f32 = 0.0;
+
 impl Camera {
+    pub fn new(eye: impl Into<cgmath::Point3<f32>>, aspect: f32) -> Self {
+        let eye = eye.into();
+        Self {
+            eye,
+            target: eye - cgmath::Vector3::unit_z(),
+            up: cgmath::Vector3::unit_y(),
+            aspect,
+            fovy: 45.0,
+            fov_axis: FovAxis::Vertical,
+            znear: 0.1,
+            zfar: 100.0,
+            focus_distance: DEFAULT_FOCUS_DISTANCE,
+            aperture: DEFAULT_APERTURE,
+        }
+    }
+
+    /// A camera `radius` away from `target` and looking at it, placed by angles.
+    ///
+    /// `azimuth` turns around +Y, starting from +Z toward +X, and `elevation` tilts up from
+    /// the XZ plane, so zero for both looks down -Z like `new`. Straight up or down views
+    /// (±90° elevation) are parallel to `up` and can't be oriented.
+    pub fn from_spherical(
+        target: impl Into<cgmath::Point3<f32>>,
+        radius: f32,
+        azimuth: impl Into<cgmath::Rad<f32>>,
+        elevation: impl Into<cgmath::Rad<f32>>,
+        aspect: f32,
+    ) -> Self {
+        let target = target.into();
+        let (azimuth, elevation) = (azimuth.into().0, elevation.into().0);
+        let offset = cgmath::Vector3::new(
+            elevation.cos() * azimuth.sin(),
+            elevation.sin(),
+            elevation.cos() * azimuth.cos(),
+        );
+
+        Self::new(target + offset * radius, aspect).looking_at(target)
+    }
+
+    pub fn looking_at(mut self, target: impl Into<cgmath::Point3<f32>>) -> Self {
+        self.target = target.into();
+        self
+    }
+
+    /// Set `fovy`, in degrees.
+    pub fn with_fovy(mut self, fovy: f32) -> Self {
+        self.fovy = fovy;
+        self
+    }
+
+    pub fn with_fov_axis(mut self, fov_axis: FovAxis) -> Self {
+        self.fov_axis = fov_axis;
+        self
+    }
+
+    /// `fovy` in radians.
+    pub fn fovy_radians(&self) -> f32 {
+        self.fovy.to_radians()
+    }
+
+    pub fn set_fovy_degrees(&mut self, degrees: f32) {
+        self.fovy = degrees;
+    }
+
+    pub fn set_fovy_radians(&mut self, radians: f32) {
+        self.fovy = radians.to_degrees();
+    }
+
+    pub fn with_clip_planes(mut self, znear: f32, zfar: f32) -> Self {
+        self.set_clip_planes(znear, zfar);
+        self
+    }
+
+    /// Panics unless `0 < znear < zfar`, since anything else breaks the projection matrix.
+    pub fn set_clip_planes(&mut self, znear: f32, zfar: f32) {
+        assert!(
+            0.0 < znear && znear < zfar,
+            "invalid clip planes: near {} and far {} must satisfy 0 < near < far",
+            znear,
+            zfar
+        );
+        self.znear = znear;
+        self.zfar = zfar;
+    }
+
+    /// Unit direction the camera looks in.
+    pub fn forward(&self) -> cgmath::Vector3<f32> {
+        use cgmath::InnerSpace;
+
+        (self.target - self.eye).normalize()
+    }
+
+    /// Rotate `up` around the forward direction. Positive angles bank clockwise as seen
+    /// through the camera, tipping the view's top toward its right.
+    pub fn roll(&mut self, angle: impl Into<cgmath::Rad<f32>>) {
+        use cgmath::{InnerSpace, Rotation3};
+
+        let forward = self.forward();
+        // Only the part of `up` perpendicular to the view direction matters
+        let up = (self.up - forward * self.up.dot(forward)).normalize();
+        self.up = cgmath::Quaternion::from_axis_angle(forward, angle) * up;
+    }
+
+    /// The point `focus_distance` in front of the eye.
+    pub fn focal_point(&self) -> cgmath::Point3<f32> {
+        self.eye + self.forward() * self.focus_distance
+    }
+
+    /// Keep looking in the same direction but move the eye and target so the box from `min`
+    /// to `max` fills as much of the view as it can while staying entirely visible.
+    ///
+    /// The clip planes aren't changed, so very large boxes may still be clipped by `zfar`.
+    /// The eye stays at least `znear * 2` from the center, so a box shrunk to a single point
+    /// keeps a valid forward direction and isn't clipped by `znear`.
+    pub fn frame_bounds(&mut self, min: cgmath::Point3<f32>, max: cgmath::Point3<f32>) {
+        use cgmath::{EuclideanSpace, InnerSpace};
+
+        let center = min.midpoint(max);
+        let radius = (max - min).magnitude() / 2.0;
+
+        // The box's bounding sphere has to fit in the narrower of the two fields of view
+        let half_vertical = self.vertical_fov().to_radians() / 2.0;
+        let half_horizontal = (half_vertical.tan() * self.aspect).atan();
+        let distance = (radius / half_vertical.min(half_horizontal).sin()).max(self.znear * 2.0);
+
+        let forward = self.forward();
+        self.target = center;
+        self.eye = center - forward * distance;
+    }
+
+    /// The vertical field of view in degrees the projection uses, derived from `fovy` and
+    /// `aspect` when the horizontal field of view is the fixed one.
+    pub fn vertical_fov(&self) -> f32 {
+        match self.fov_axis {
+            FovAxis::Vertical => self.fovy,
+            FovAxis::Horizontal => {
+                let half_horizontal = self.fovy.to_radians() / 2.0;
+                (2.0 * (half_horizontal.tan() / self.aspect).atan()).to_degrees()
+            }
+        }
+    }
+
     pub fn build_view_projection_matrix(&self) -> cgmath::Matrix4<f32> {
-        let target = (self.eye.x, self.eye.y, self.eye.z - 1.0).into();
-        let view = cgmath::Matrix4::look_at(self.eye, target, self.up);
-        let proj = cgmath::perspective(cgmath::Deg(self.fovy), self.aspect, self.znear, self.zfar);
+        let view = cgmath::Matrix4::look_at(self.eye, self.target, self.up);
+        let proj = cgmath::perspective(cgmath::Deg(self.vertical_fov()), self.aspect, self.znear, self.zfar);
         return OPENGL_TO_WGPU_MATRIX * proj * view;
     }
+
+    /// The pixel a world space point projects to, measured from the top left corner of a
+    /// `width` by `height` viewport like `screen_ray`. `None` for points behind the camera;
+    /// points outside the view give pixels outside the viewport.
+    pub fn world_to_screen(&self, point: cgmath::Point3<f32>, width: f32, height: f32) -> Option<Vector2f> {
+        let clip = self.build_view_projection_matrix() * point.to_homogeneous();
+        if clip.w <= 0.0 {
+            return None;
+        }
+
+        let ndc_x = clip.x / clip.w;
+        let ndc_y = clip.y / clip.w;
+        Some(Vector2f::new(
+            ((ndc_x + 1.0) / 2.0 * width) as f64,
+            ((1.0 - ndc_y) / 2.0 * height) as f64,
+        ))
+    }
+
+    /// World space ray through a pixel, as an origin on the near plane and a unit direction.
+    ///
+    /// `pixel` is measured from the top left corner of a `width` by `height` viewport.
+    pub fn screen_ray(&self, pixel: &Vector2f, width: f32, height: f32) -> (cgmath::Point3<f32>, cgmath::Vector3<f32>) {
+        use cgmath::{InnerSpace, SquareMatrix};
+
+        let ndc_x = 2.0 * pixel.x as f32 / width - 1.0;
+        let ndc_y = 1.0 - 2.0 * pixel.y as f32 / height;
+
+        // The projection is invertible for any camera with 0 < znear < zfar
+        let inverse = self
+            .build_view_projection_matrix()
+            .invert()
+            .expect("view projection matrix is not invertible");
+
+        // OPENGL_TO_WGPU_MATRIX maps depth to wgpu's 0..1, so the near plane is at 0 not -1
+        let unproject = |ndc_z: f32| {
+            let point = inverse * cgmath::Vector4::new(ndc_x, ndc_y, ndc_z, 1.0);
+            cgmath::Point3::new(point.x / point.w, point.y / point.w, point.z / point.w)
+        };
+        let near = unproject(0.0);
+        let far = unproject(1.0);
+
+        (near, (far - near).normalize())
+    }
 }
 
 #[rustfmt::skip]
@@ -26,26 +236,338 @@ pub const OPENGL_TO_WGPU_MATRIX: cgmath::Matrix4<f32> = cgmath::Matrix4::new(
     0.0, 0.0, 0.5, 1.0,
 );
 
+/// An orthographic camera for screen space overlays like HUDs.
+///
+/// Positions are in physical pixels from the bottom left corner of the window with +Y up,
+/// so geometry keeps the same winding as in world space. Only z between `znear` and
+/// `zfar` in front of the camera is drawn.
+#[derive(Debug, Clone, Copy)]
+pub struct OverlayCamera {
+    pub znear: f32,
+    pub zfar: f32,
+}
+
+impl OverlayCamera {
+    pub fn build_projection_matrix(&self, width: f32, height: f32) -> cgmath::Matrix4<f32> {
+        OPENGL_TO_WGPU_MATRIX * cgmath::ortho(0.0, width, 0.0, height, self.znear, self.zfar)
+    }
+}
+
+impl Default for OverlayCamera {
+    /// Draws z from -1 to 1, so flat overlays at z = 0 are always visible.
+    fn default() -> Self {
+        Self {
+            znear: -1.0,
+            zfar: 1.0,
+        }
+    }
+}
+
+/// The axis whose field of view a `Camera` keeps fixed as its aspect ratio changes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FovAxis {
+    /// Wider windows see more horizontally.
+    Vertical,
+    /// Wider windows see less vertically, so ultrawide screens don't stretch the view.
+    Horizontal,
+}
+
+/// How `CameraController` input axes map onto camera movement.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MovementMode {
+    /// Axes move along the world X, Y and Z axes.
+    Fly,
+    /// Up/down input walks along the camera's horizontal forward direction and left/right
+    /// strafes, keeping the eye at a fixed height.
+    FirstPerson,
+}
+
+/// Which speed modifier actions are held, passed to a `CameraController` speed curve.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SpeedModifiers {
+    /// A `CameraAction::Fast` key is held.
+    pub fast: bool,
+    /// A `CameraAction::Slow` key is held.
+    pub slow: bool,
+}
+
+/// A camera move in progress, started by `CameraController::animate_to`.
+struct CameraAnimation {
+    /// Where the eye and target were when the animation started; filled in on the first
+    /// update since the controller doesn't have the camera until then.
+    from: Option<(cgmath::Point3<f32>, cgmath::Point3<f32>, Instant)>,
+    to: cgmath::Point3<f32>,
+    target_to: cgmath::Point3<f32>,
+    duration: Duration,
+}
+
+/// An instance the camera is following, set by `CameraController::follow`.
+struct CameraFollow {
+    object_id: usize,
+    instance_id: usize,
+    offset: cgmath::Vector3<f32>,
+    smoothing: f32,
+}
+
+/// Degrees `fovy` changes by per line scrolled.
+const ZOOM_DEGREES_PER_LINE: f32 = 2.0;
+/// Scrolled pixels, from touchpads, that count as one line.
+const PIXELS_PER_LINE: f32 = 20.0;
+
+/// Smoothstep easing, slow at both ends.
+fn ease_in_out(t: f32) -> f32 {
+    t * t * (3.0 - 2.0 * t)
+}
+
 pub struct CameraController {
+    enabled: bool,
     speed: f32,
+    movement_mode: MovementMode,
     x_axis: f32,
     y_axis: f32,
     z_axis: f32,
     speed_multiplier: f32,
+    fast_multiplier: f32,
+    slow_multiplier: f32,
+    fast_held: bool,
+    slow_held: bool,
+    // Replaces the fast/slow multipliers when set
+    speed_curve: Option<Box<dyn FnMut(SpeedModifiers) -> f32>>,
+    // -1 to roll left, 1 to roll right
+    roll_axis: f32,
+    // Radians rolled per update while a roll key is held
+    roll_speed: f32,
+    key_bindings: KeyBindings,
+    animation: Option<CameraAnimation>,
+    follow: Option<CameraFollow>,
+    mouse_captured: bool,
+    // Lines scrolled since the last update, positive to zoom in
+    zoom_lines: f32,
+    min_fovy: f32,
+    max_fovy: f32,
 }
 
 impl CameraController {
     pub fn new(speed: f32) -> Self {
         Self {
+            enabled: true,
             speed,
+            movement_mode: MovementMode::Fly,
             x_axis: 0.0,
             y_axis: 0.0,
             z_axis: 0.0,
             speed_multiplier: 1.0,
+            fast_multiplier: 2.0,
+            slow_multiplier: 0.25,
+            fast_held: false,
+            slow_held: false,
+            speed_curve: None,
+            roll_axis: 0.0,
+            roll_speed: 0.03,
+            key_bindings: KeyBindings::default(),
+            animation: None,
+            follow: None,
+            mouse_captured: false,
+            zoom_lines: 0.0,
+            min_fovy: 10.0,
+            max_fovy: 120.0,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// While disabled the controller ignores input and leaves the camera alone, so the app
+    /// can move it directly. Held input is dropped either way.
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        self.reset();
+    }
+
+    pub fn speed(&self) -> f32 {
+        self.speed
+    }
+
+    pub fn set_speed(&mut self, speed: f32) {
+        self.speed = speed;
+    }
+
+    pub fn movement_mode(&self) -> MovementMode {
+        self.movement_mode
+    }
+
+    pub fn set_movement_mode(&mut self, movement_mode: MovementMode) {
+        self.movement_mode = movement_mode;
+    }
+
+    pub fn speed_multiplier(&self) -> f32 {
+        self.speed_multiplier
+    }
+
+    /// Override the multiplier applied to `speed`. The next fast/slow key event replaces it,
+    /// or the next update if there's a speed curve.
+    pub fn set_speed_multiplier(&mut self, speed_multiplier: f32) {
+        self.speed_multiplier = speed_multiplier;
+    }
+
+    /// Multiplier used while a `CameraAction::Fast` key is held.
+    pub fn set_fast_multiplier(&mut self, fast_multiplier: f32) {
+        self.fast_multiplier = fast_multiplier;
+        self.update_speed_multiplier();
+    }
+
+    /// Multiplier used while a `CameraAction::Slow` key is held.
+    pub fn set_slow_multiplier(&mut self, slow_multiplier: f32) {
+        self.slow_multiplier = slow_multiplier;
+        self.update_speed_multiplier();
+    }
+
+    /// Compute the speed multiplier with `speed_curve` instead of the fast/slow multipliers.
+    /// It's called on every update with the held modifiers, so it can also follow state of
+    /// the app's own, like a speed set with the scroll wheel. `None` restores the default.
+    pub fn set_speed_curve(&mut self, speed_curve: Option<Box<dyn FnMut(SpeedModifiers) -> f32>>) {
+        self.speed_curve = speed_curve;
+        self.update_speed_multiplier();
+    }
+
+    /// Radians the camera rolls per update while a roll key is held.
+    pub fn roll_speed(&self) -> f32 {
+        self.roll_speed
+    }
+
+    pub fn set_roll_speed(&mut self, roll_speed: f32) {
+        self.roll_speed = roll_speed;
+    }
+
+    /// Narrowest and widest `fovy` scroll zoom can reach, in degrees.
+    pub fn fovy_limits(&self) -> (f32, f32) {
+        (self.min_fovy, self.max_fovy)
+    }
+
+    pub fn set_fovy_limits(&mut self, min_fovy: f32, max_fovy: f32) {
+        self.min_fovy = min_fovy;
+        self.max_fovy = max_fovy;
+    }
+
+    pub fn key_bindings(&self) -> &KeyBindings {
+        &self.key_bindings
+    }
+
+    pub fn key_bindings_mut(&mut self) -> &mut KeyBindings {
+        &mut self.key_bindings
+    }
+
+    /// Smoothly move the eye to `eye` and the point it looks at to `target_look` over
+    /// `duration`, starting on the next update.
+    ///
+    /// Manual input is ignored until the animation finishes, then control returns to the
+    /// user. Starting another animation replaces the current one from wherever the camera is.
+    pub fn animate_to(
+        &mut self,
+        eye: impl Into<cgmath::Point3<f32>>,
+        target_look: impl Into<cgmath::Point3<f32>>,
+        duration: Duration,
+    ) {
+        self.animation = Some(CameraAnimation {
+            from: None,
+            to: eye.into(),
+            target_to: target_look.into(),
+            duration,
+        });
+    }
+
+    pub fn is_animating(&self) -> bool {
+        self.animation.is_some()
+    }
+
+    pub fn speed_modifiers(&self) -> SpeedModifiers {
+        SpeedModifiers {
+            fast: self.fast_held,
+            slow: self.slow_held,
         }
     }
 
+    /// Grab and hide the cursor for mouse-look, or release and show it again. While it's
+    /// captured `State::handle_event` moves it back to the middle of `window` every frame,
+    /// so it never runs into the edge of the screen.
+    pub fn set_mouse_captured(&mut self, window: &Window, captured: bool) -> Result<(), ExternalError> {
+        window.set_cursor_grab(captured)?;
+        window.set_cursor_visible(!captured);
+        self.mouse_captured = captured;
+        Ok(())
+    }
+
+    pub fn is_mouse_captured(&self) -> bool {
+        self.mouse_captured
+    }
+
+    /// Move the cursor to the middle of `window` if the mouse is captured. Fails on
+    /// platforms that don't let apps move the cursor, like Wayland.
+    pub fn recenter_cursor(&self, window: &Window) -> Result<(), ExternalError> {
+        if !self.mouse_captured {
+            return Ok(());
+        }
+
+        let size = window.inner_size();
+        window.set_cursor_position(PhysicalPosition::new(size.width / 2, size.height / 2))
+    }
+
+    /// Keep the eye at `offset` from an instance and looking at it as it moves, until
+    /// `stop_following`, the instance is removed, or `update_camera` is called.
+    ///
+    /// `smoothing`, from `0.0` to `1.0`, is the fraction of its distance to the goal the eye
+    /// keeps each update: `0.0` sticks to the instance, values near `1.0` lag further behind.
+    /// Movement keys nudge the offset instead of moving the camera. `State::update` looks up
+    /// the instance; when calling the controller directly use `update_camera_following`, since
+    /// `update_camera` has no position to follow and stops following.
+    pub fn follow(&mut self, object_id: usize, instance_id: usize, offset: cgmath::Vector3<f32>, smoothing: f32) {
+        self.follow = Some(CameraFollow {
+            object_id,
+            instance_id,
+            offset,
+            smoothing,
+        });
+    }
+
+    pub fn stop_following(&mut self) {
+        self.follow = None;
+    }
+
+    /// The object and instance ids being followed.
+    pub fn follow_target(&self) -> Option<(usize, usize)> {
+        self.follow
+            .as_ref()
+            .map(|follow| (follow.object_id, follow.instance_id))
+    }
+
+    /// The current offset from the followed instance, including any nudges.
+    pub fn follow_offset(&self) -> Option<cgmath::Vector3<f32>> {
+        self.follow.as_ref().map(|follow| follow.offset)
+    }
+
+    /// When both fast and slow keys are held, slow takes precedence.
+    fn update_speed_multiplier(&mut self) {
+        let modifiers = self.speed_modifiers();
+        if let Some(speed_curve) = &mut self.speed_curve {
+            self.speed_multiplier = speed_curve(modifiers);
+            return;
+        }
+
+        self.speed_multiplier = if self.slow_held {
+            self.slow_multiplier
+        } else if self.fast_held {
+            self.fast_multiplier
+        } else {
+            1.0
+        };
+    }
+
     pub fn process_events(&mut self, event: &WindowEvent) -> bool {
+        if !self.enabled {
+            return false;
+        }
+
         match event {
             WindowEvent::KeyboardInput {
                 input:
@@ -58,49 +580,207 @@ impl CameraController {
             } => {
                 let is_pressed = *state == ElementState::Pressed;
                 let axis_value = if is_pressed { 1.0 } else { 0.0 };
-                match keycode {
-                    VirtualKeyCode::A | VirtualKeyCode::Left => {
-                        self.x_axis = -axis_value;
-                        true
-                    }
-                    VirtualKeyCode::D | VirtualKeyCode::Right => {
-                        self.x_axis = axis_value;
-                        true
-                    }
-                    VirtualKeyCode::W | VirtualKeyCode::Up => {
-                        self.y_axis = axis_value;
-                        true
+                let action = match self.key_bindings.action(*keycode) {
+                    Some(action) => action,
+                    None => return false,
+                };
+                match action {
+                    CameraAction::Left => self.x_axis = -axis_value,
+                    CameraAction::Right => self.x_axis = axis_value,
+                    CameraAction::Up => self.y_axis = axis_value,
+                    CameraAction::Down => self.y_axis = -axis_value,
+                    CameraAction::Forward => self.z_axis = -axis_value,
+                    CameraAction::Backward => self.z_axis = axis_value,
+                    CameraAction::Fast => {
+                        self.fast_held = is_pressed;
+                        self.update_speed_multiplier();
                     }
-                    VirtualKeyCode::S | VirtualKeyCode::Down => {
-                        self.y_axis = -axis_value;
-                        true
+                    CameraAction::Slow => {
+                        self.slow_held = is_pressed;
+                        self.update_speed_multiplier();
                     }
-                    VirtualKeyCode::R | VirtualKeyCode::E => {
-                        self.z_axis = -axis_value;
-                        true
-                    }
-                    VirtualKeyCode::F | VirtualKeyCode::Q => {
-                        self.z_axis = axis_value;
-                        true
-                    }
-                    VirtualKeyCode::LShift | VirtualKeyCode::RShift => {
-                        self.speed_multiplier = if is_pressed { 2.0 } else { 1.0 };
-                        true
-                    }
-                    VirtualKeyCode::LAlt | VirtualKeyCode::RAlt => {
-                        self.speed_multiplier = if is_pressed { 0.25 } else { 1.0 };
-                        true
-                    }
-                    _ => false,
+                    CameraAction::RollLeft => self.roll_axis = -axis_value,
+                    CameraAction::RollRight => self.roll_axis = axis_value,
                 }
+                true
+            }
+            // Scrolling zooms by narrowing or widening the field of view
+            WindowEvent::MouseWheel { delta, .. } => {
+                self.zoom_lines += match delta {
+                    MouseScrollDelta::LineDelta(_, lines) => *lines,
+                    MouseScrollDelta::PixelDelta(position) => position.y as f32 / PIXELS_PER_LINE,
+                };
+                true
+            }
+            // Key releases are missed while unfocused, so drop any held input
+            WindowEvent::Focused(false) => {
+                self.reset();
+                true
             }
             _ => false,
         }
     }
 
-    pub fn update_camera(&self, camera: &mut Camera) {
-        camera.eye.x += self.x_axis * self.speed * self.speed_multiplier;
-        camera.eye.y += self.y_axis * self.speed * self.speed_multiplier;
-        camera.eye.z += self.z_axis * self.speed * self.speed_multiplier;
+    /// Clear all movement, roll and zoom input and the speed multiplier.
+    pub fn reset(&mut self) {
+        self.x_axis = 0.0;
+        self.y_axis = 0.0;
+        self.z_axis = 0.0;
+        self.roll_axis = 0.0;
+        self.zoom_lines = 0.0;
+        self.fast_held = false;
+        self.slow_held = false;
+        self.speed_multiplier = 1.0;
+    }
+
+    /// Move `camera` by the held input.
+    ///
+    /// Following needs the followed instance's position, so this clears any `follow` as if the
+    /// instance had been removed, and `follow_target` returns `None` afterwards. Use
+    /// `update_camera_following` to keep following.
+    pub fn update_camera(&mut self, camera: &mut Camera) {
+        self.update_camera_following(camera, None);
+    }
+
+    /// Update with `target_position`, the position of the `follow_target` instance, or
+    /// `None` if it doesn't exist anymore.
+    pub fn update_camera_following(&mut self, camera: &mut Camera, target_position: Option<cgmath::Point3<f32>>) {
+        use cgmath::InnerSpace;
+
+        if !self.enabled {
+            return;
+        }
+
+        if let Some(animation) = &mut self.animation {
+            let (from, target_from, start) = *animation
+                .from
+                .get_or_insert_with(|| (camera.eye, camera.target, Instant::now()));
+            let (animation_to, target_to) = (animation.to, animation.target_to);
+            let t = if animation.duration == Duration::from_secs(0) {
+                1.0
+            } else {
+                (start.elapsed().as_secs_f32() / animation.duration.as_secs_f32()).min(1.0)
+            };
+
+            if t >= 1.0 {
+                self.animation = None;
+                camera.eye = animation_to;
+                camera.target = target_to;
+            } else {
+                let eased = ease_in_out(t);
+                camera.eye = from + (animation_to - from) * eased;
+                camera.target = target_from + (target_to - target_from) * eased;
+            }
+            return;
+        }
+
+        if self.zoom_lines != 0.0 {
+            let fovy = camera.fovy - self.zoom_lines * ZOOM_DEGREES_PER_LINE;
+            camera.fovy = fovy.max(self.min_fovy).min(self.max_fovy);
+            self.zoom_lines = 0.0;
+        }
+
+        if self.roll_axis != 0.0 {
+            camera.roll(cgmath::Rad(self.roll_axis * self.roll_speed));
+        }
+
+        if self.speed_curve.is_some() {
+            self.update_speed_multiplier();
+        }
+        let speed = self.speed * self.speed_multiplier;
+
+        if let Some(follow) = &mut self.follow {
+            let target = match target_position {
+                Some(target) => target,
+                None => {
+                    self.follow = None;
+                    return;
+                }
+            };
+
+            follow.offset += cgmath::Vector3::new(self.x_axis, self.y_axis, self.z_axis) * speed;
+            let goal = target + follow.offset;
+            camera.eye = goal + (camera.eye - goal) * follow.smoothing;
+            camera.target = target;
+            return;
+        }
+
+        let movement = match self.movement_mode {
+            MovementMode::Fly => cgmath::Vector3::new(self.x_axis, self.y_axis, self.z_axis) * speed,
+            MovementMode::FirstPerson => {
+                let forward = camera.forward();
+                let forward = cgmath::Vector3::new(forward.x, 0.0, forward.z);
+                if forward.magnitude2() == 0.0 {
+                    // Looking straight up or down, there's no horizontal direction to walk in
+                    return;
+                }
+                let forward = forward.normalize();
+                let right = forward.cross(cgmath::Vector3::unit_y());
+
+                // Forward/backward keys walk too, since there's no flying
+                let forward_amount = self.y_axis - self.z_axis;
+                (forward * forward_amount + right * self.x_axis) * speed
+            }
+        };
+
+        camera.eye += movement;
+        camera.target += movement;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cgmath::InnerSpace;
+
+    const EPSILON: f32 = 1e-4;
+
+    #[test]
+    fn screen_ray_through_center_looks_forward() {
+        let camera = Camera::new((0.0, 0.0, 5.0), 800.0 / 600.0);
+        let (origin, direction) = camera.screen_ray(&Vector2f::new(400.0, 300.0), 800.0, 600.0);
+
+        assert!((direction - cgmath::Vector3::new(0.0, 0.0, -1.0)).magnitude() < EPSILON);
+        let near = cgmath::Point3::new(0.0, 0.0, 5.0 - camera.znear);
+        assert!((origin - near).magnitude() < EPSILON, "origin {:?} is not on the near plane", origin);
+    }
+
+    #[test]
+    fn screen_ray_round_trips_through_world_to_screen() {
+        let camera = Camera::new((0.0, 0.0, 5.0), 800.0 / 600.0);
+        let pixel = Vector2f::new(123.0, 456.0);
+        let (origin, direction) = camera.screen_ray(&pixel, 800.0, 600.0);
+
+        let screen = camera
+            .world_to_screen(origin + direction * 3.0, 800.0, 600.0)
+            .expect("point along the ray is behind the camera");
+        assert!((screen.x - pixel.x).abs() < 0.01, "x {} != {}", screen.x, pixel.x);
+        assert!((screen.y - pixel.y).abs() < 0.01, "y {} != {}", screen.y, pixel.y);
+    }
+
+    #[test]
+    fn frame_bounds_of_a_point_keeps_a_distance() {
+        let mut camera = Camera::new((0.0, 0.0, 5.0), 800.0 / 600.0);
+        let point = cgmath::Point3::new(1.0, 2.0, 3.0);
+        camera.frame_bounds(point, point);
+
+        assert_eq!(camera.target, point);
+        assert!(((camera.eye - camera.target).magnitude() - camera.znear * 2.0).abs() < EPSILON);
+        let forward = camera.forward();
+        assert!((forward - cgmath::Vector3::new(0.0, 0.0, -1.0)).magnitude() < EPSILON, "forward {:?}", forward);
+    }
+
+    #[test]
+    fn update_camera_stops_following() {
+        let mut camera = Camera::new((0.0, 0.0, 5.0), 800.0 / 600.0);
+        let mut controller = CameraController::new(1.0);
+        controller.follow(1, 2, cgmath::Vector3::new(0.0, 0.0, 5.0), 0.0);
+
+        controller.update_camera_following(&mut camera, Some(cgmath::Point3::new(1.0, 0.0, 0.0)));
+        assert_eq!(controller.follow_target(), Some((1, 2)));
+        assert_eq!(camera.target, cgmath::Point3::new(1.0, 0.0, 0.0));
+
+        controller.update_camera(&mut camera);
+        assert_eq!(controller.follow_target(), None);
     }
 }
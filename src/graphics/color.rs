@@ -1,6 +1,7 @@
 use crate::common::math;
 use std::ops::Range;
 
+/// An RGB color, linear unless `GraphicsConfig::srgb_colors` is set.
 pub type Color = [f32; 3];
 
 pub const BLACK: Color = [0.0, 0.0, 0.0];
@@ -1,8 +1,38 @@
+/// Colors are linear unless noted otherwise: the default sRGB surface format encodes the
+/// shaders' linear output for display, so a color of 0.5 is not the sRGB gray #808080.
 #[derive(Clone, Debug)]
 pub struct GraphicsConfig {
+    /// Linear, whatever `srgb_colors` is.
     pub clear_color: wgpu::Color,
+    /// Maximum anisotropic filtering level for samplers, or `None` to leave it disabled.
+    /// Ignored if the adapter doesn't support anisotropic filtering.
+    pub max_anisotropy: Option<u8>,
+    /// Present modes in order of preference; the first one the backend supports is used,
+    /// falling back to `Fifo`.
+    pub present_modes: Vec<wgpu::PresentMode>,
+    /// Swap chain format, also used by every render pipeline. Must be one of the 8 bit
+    /// BGRA/RGBA formats; anything else falls back to `DEFAULT_SURFACE_FORMAT`.
+    pub surface_format: wgpu::TextureFormat,
+    /// Samples per pixel for multisample anti-aliasing: 1, 2, 4 or 8, with 1 turning it
+    /// off. Other counts fall back to 1. `PipelineConfig::alpha_to_coverage` and
+    /// `sample_mask` only have an effect with more than one sample.
+    pub sample_count: u32,
+    /// Most instances a new object can have, or `None` for no limit. Change it for a
+    /// single object with `State::set_max_instances`.
+    pub max_instances: Option<usize>,
+    /// Treat vertex, instance tint and material colors as sRGB, as picked in most color
+    /// pickers and image editors, and convert them to linear before lighting. Light colors
+    /// and `clear_color` are always linear.
+    pub srgb_colors: bool,
+    /// Where the camera starts. By default it's 50 units back along +Z and slightly above
+    /// the origin, looking down -Z.
+    pub initial_eye: cgmath::Point3<f32>,
+    pub initial_target: cgmath::Point3<f32>,
 }
 
+/// The surface format every primary backend supports.
+pub const DEFAULT_SURFACE_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Bgra8UnormSrgb;
+
 impl Default for GraphicsConfig {
     fn default() -> Self {
         Self {
@@ -12,6 +42,14 @@ impl Default for GraphicsConfig {
                 b: 0.0,
                 a: 1.0,
             },
+            max_anisotropy: None,
+            present_modes: vec![wgpu::PresentMode::Fifo],
+            surface_format: DEFAULT_SURFACE_FORMAT,
+            sample_count: 1,
+            max_instances: None,
+            srgb_colors: false,
+            initial_eye: cgmath::Point3::new(0.0, 1.0, 50.0),
+            initial_target: cgmath::Point3::new(0.0, 1.0, 0.0),
         }
     }
 }
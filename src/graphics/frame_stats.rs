@@ -0,0 +1,102 @@
+use std::time::Duration;
+
+/// What the last call to `State::render` did.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct FrameStats {
+    pub draw_calls: usize,
+    /// Instances drawn after frustum culling.
+    pub instances_drawn: usize,
+    /// Triangles drawn across every instance. Line and point objects don't count.
+    pub triangles: usize,
+    /// Time since the previous frame was rendered.
+    pub frame_time: Duration,
+}
+
+/// Frame times measured by `State::bench`.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct BenchResult {
+    pub frames: usize,
+    pub total: Duration,
+    pub min: Duration,
+    pub max: Duration,
+    pub mean: Duration,
+    /// 99% of frames took this long or less.
+    pub p99: Duration,
+}
+
+impl BenchResult {
+    /// Summarize a run's frame times, or all zeros if there weren't any.
+    pub fn from_frame_times(frame_times: &[Duration]) -> Self {
+        if frame_times.is_empty() {
+            return Self::default();
+        }
+
+        let mut sorted = frame_times.to_vec();
+        sorted.sort();
+
+        let frames = sorted.len();
+        let total: Duration = sorted.iter().sum();
+        // Nearest rank, so small runs report an actual frame time
+        let p99_rank = (frames * 99 + 99) / 100;
+
+        Self {
+            frames,
+            total,
+            min: sorted[0],
+            max: sorted[frames - 1],
+            mean: total / frames as u32,
+            p99: sorted[p99_rank - 1],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Frame times of 1ms up to `frames` ms, slowest first so they have to be sorted.
+    fn millis(frames: u64) -> Vec<Duration> {
+        (1..=frames).rev().map(Duration::from_millis).collect()
+    }
+
+    #[test]
+    fn no_frames_is_all_zeros() {
+        assert_eq!(BenchResult::from_frame_times(&[]), BenchResult::default());
+    }
+
+    #[test]
+    fn one_frame() {
+        let result = BenchResult::from_frame_times(&[Duration::from_millis(7)]);
+
+        assert_eq!(result.frames, 1);
+        assert_eq!(result.total, Duration::from_millis(7));
+        assert_eq!(result.min, Duration::from_millis(7));
+        assert_eq!(result.max, Duration::from_millis(7));
+        assert_eq!(result.mean, Duration::from_millis(7));
+        assert_eq!(result.p99, Duration::from_millis(7));
+    }
+
+    #[test]
+    fn hundred_frames() {
+        let result = BenchResult::from_frame_times(&millis(100));
+
+        assert_eq!(result.frames, 100);
+        assert_eq!(result.total, Duration::from_millis(5050));
+        assert_eq!(result.min, Duration::from_millis(1));
+        assert_eq!(result.max, Duration::from_millis(100));
+        assert_eq!(result.mean, Duration::from_micros(50_500));
+        // The 99th of 100 frames
+        assert_eq!(result.p99, Duration::from_millis(99));
+    }
+
+    #[test]
+    fn hundred_and_one_frames() {
+        let result = BenchResult::from_frame_times(&millis(101));
+
+        assert_eq!(result.frames, 101);
+        assert_eq!(result.max, Duration::from_millis(101));
+        assert_eq!(result.mean, Duration::from_millis(51));
+        // 99% of 101 frames is 99.99, so the 100th
+        assert_eq!(result.p99, Duration::from_millis(100));
+    }
+}
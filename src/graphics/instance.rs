@@ -1,23 +1,59 @@
+use crate::common::math::Transform;
+
 #[derive(Clone, Debug)]
 pub struct Instance {
     pub position: cgmath::Vector3<f32>,
     pub rotation: cgmath::Quaternion<f32>,
+    pub scale: cgmath::Vector3<f32>,
+    /// RGBA tint multiplied into the object's vertex colors.
+    pub color: [f32; 4],
 }
 
 impl Instance {
+    pub fn new(position: cgmath::Vector3<f32>, rotation: cgmath::Quaternion<f32>) -> Self {
+        Self {
+            position,
+            rotation,
+            scale: cgmath::Vector3::new(1.0, 1.0, 1.0),
+            color: [1.0, 1.0, 1.0, 1.0],
+        }
+    }
+
+    pub fn from_transform(transform: &Transform) -> Self {
+        Self {
+            position: transform.translation,
+            rotation: transform.rotation,
+            scale: transform.scale,
+            color: [1.0, 1.0, 1.0, 1.0],
+        }
+    }
+
+    pub fn transform(&self) -> Transform {
+        Transform::new(self.position, self.rotation, self.scale)
+    }
+
+    /// The GPU representation, with an `object_id` of 0 for the owning object to fill in.
     pub fn to_raw(&self) -> InstanceRaw {
         InstanceRaw {
-            model: cgmath::Matrix4::from_translation(self.position)
-                * cgmath::Matrix4::from(self.rotation),
+            model: self.transform().matrix().into(),
+            color: self.color,
+            object_id: 0,
+            _padding: [0; 3],
         }
     }
 }
 
 #[repr(C)]
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct InstanceRaw {
-    pub model: cgmath::Matrix4<f32>,
+    // Stored as plain arrays since cgmath's matrices aren't Pod
+    pub model: [[f32; 4]; 4],
+    pub color: [f32; 4],
+    /// Id of the owning object plus one, written out by the picking pass so 0 means nothing.
+    pub object_id: u32,
+    // std430 rounds the struct up to a multiple of 16 bytes
+    _padding: [u32; 3],
 }
 
-unsafe impl bytemuck::Pod for InstanceRaw {}
-unsafe impl bytemuck::Zeroable for InstanceRaw {}
+// Must match the array stride of the instance buffer in basic.vert and pick.vert
+const _: () = assert!(std::mem::size_of::<InstanceRaw>() == 96);
@@ -0,0 +1,84 @@
+use std::collections::HashMap;
+use winit::event::VirtualKeyCode;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CameraAction {
+    Left,
+    Right,
+    Up,
+    Down,
+    Forward,
+    Backward,
+    Fast,
+    Slow,
+    /// Bank the camera counter-clockwise around its forward direction.
+    RollLeft,
+    /// Bank the camera clockwise around its forward direction.
+    RollRight,
+}
+
+/// Maps keys to the camera actions they trigger.
+#[derive(Debug, Clone)]
+pub struct KeyBindings {
+    bindings: HashMap<VirtualKeyCode, CameraAction>,
+}
+
+impl KeyBindings {
+    /// Bindings with no keys bound.
+    pub fn empty() -> Self {
+        Self {
+            bindings: HashMap::new(),
+        }
+    }
+
+    /// Bind `key` to `action`, replacing whatever it was bound to before.
+    pub fn bind(&mut self, key: VirtualKeyCode, action: CameraAction) {
+        self.bindings.insert(key, action);
+    }
+
+    pub fn unbind(&mut self, key: VirtualKeyCode) {
+        self.bindings.remove(&key);
+    }
+
+    /// Remove every key bound to `action`.
+    pub fn unbind_action(&mut self, action: CameraAction) {
+        self.bindings.retain(|_, bound| *bound != action);
+    }
+
+    pub fn action(&self, key: VirtualKeyCode) -> Option<CameraAction> {
+        self.bindings.get(&key).copied()
+    }
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        use CameraAction::*;
+        use VirtualKeyCode as Key;
+
+        let mut bindings = Self::empty();
+        for &(key, action) in &[
+            (Key::A, Left),
+            (Key::Left, Left),
+            (Key::D, Right),
+            (Key::Right, Right),
+            (Key::W, Up),
+            (Key::Up, Up),
+            (Key::S, Down),
+            (Key::Down, Down),
+            (Key::R, Forward),
+            (Key::E, Forward),
+            (Key::F, Backward),
+            (Key::Q, Backward),
+            (Key::LShift, Fast),
+            (Key::RShift, Fast),
+            (Key::LAlt, Slow),
+            (Key::RAlt, Slow),
+            (Key::Z, RollLeft),
+            (Key::C, RollRight),
+        ] {
+            bindings.bind(key, action);
+        }
+
+        bindings
+    }
+}
@@ -0,0 +1,21 @@
+use serde::{Deserialize, Serialize};
+
+/// The render pass an object is drawn in.
+///
+/// Each layer in use gets its own pass, lowest first. The first pass clears the frame
+/// to the clear color and every later pass draws over what is already there, so for
+/// example a skybox, the scene and a UI overlay can be layered without depth testing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct Layer(pub i32);
+
+impl Layer {
+    pub const BACKGROUND: Layer = Layer(-1);
+    pub const SCENE: Layer = Layer(0);
+    pub const OVERLAY: Layer = Layer(1);
+}
+
+impl Default for Layer {
+    fn default() -> Self {
+        Self::SCENE
+    }
+}
@@ -0,0 +1,146 @@
+use crate::graphics::Color;
+
+/// Most lights `State` shades with at once; any more are ignored.
+pub const MAX_LIGHTS: usize = 8;
+
+const LIGHT_AMBIENT: u32 = 0;
+const LIGHT_DIRECTIONAL: u32 = 1;
+const LIGHT_POINT: u32 = 2;
+
+/// A light source. Colors can go above 1.0 for brighter lights.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Light {
+    /// Lights every surface equally, whichever way it faces.
+    Ambient { color: Color },
+    /// Light from infinitely far away, like the sun, shining along `direction`. A zero
+    /// `direction` shines straight down.
+    Directional {
+        direction: cgmath::Vector3<f32>,
+        color: Color,
+    },
+    /// Light shining in every direction from `position`, fading with distance.
+    Point {
+        position: cgmath::Point3<f32>,
+        color: Color,
+        attenuation: Attenuation,
+    },
+}
+
+/// How a point light fades: its brightness at distance `d` is divided by
+/// `constant + linear * d + quadratic * d * d`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Attenuation {
+    pub constant: f32,
+    pub linear: f32,
+    pub quadratic: f32,
+}
+
+impl Default for Attenuation {
+    /// Down to about a tenth of full brightness 15 units away.
+    fn default() -> Self {
+        Self {
+            constant: 1.0,
+            linear: 0.09,
+            quadratic: 0.032,
+        }
+    }
+}
+
+impl Light {
+    fn to_raw(self) -> LightRaw {
+        match self {
+            Light::Ambient { color } => LightRaw {
+                position: [0.0; 4],
+                color,
+                kind: LIGHT_AMBIENT,
+                attenuation: [0.0; 4],
+            },
+            Light::Directional { direction, color } => {
+                use cgmath::InnerSpace;
+
+                // Normalizing a zero vector would give NaNs and black out every surface
+                let direction = if direction.magnitude2() > 0.0 {
+                    direction.normalize()
+                } else {
+                    -cgmath::Vector3::unit_y()
+                };
+                LightRaw {
+                    position: [direction.x, direction.y, direction.z, 0.0],
+                    color,
+                    kind: LIGHT_DIRECTIONAL,
+                    attenuation: [0.0; 4],
+                }
+            }
+            Light::Point {
+                position,
+                color,
+                attenuation,
+            } => LightRaw {
+                position: [position.x, position.y, position.z, 1.0],
+                color,
+                kind: LIGHT_POINT,
+                attenuation: [attenuation.constant, attenuation.linear, attenuation.quadratic, 0.0],
+            },
+        }
+    }
+}
+
+// Laid out like the std140 `Light` struct in basic.frag
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct LightRaw {
+    // Direction for directional lights
+    position: [f32; 4],
+    color: Color,
+    kind: u32,
+    attenuation: [f32; 4],
+}
+
+/// The lights uniform block. Shading is skipped entirely when there are no lights, so
+/// existing scenes keep their flat vertex colors.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct LightUniforms {
+    lights: [LightRaw; MAX_LIGHTS],
+    count: u32,
+    _padding: [u32; 3],
+}
+
+// Must match the uniform block in basic.frag
+const _: () = assert!(std::mem::size_of::<LightUniforms>() == 48 * MAX_LIGHTS + 16);
+
+impl LightUniforms {
+    /// Uniforms for the first `MAX_LIGHTS` of `lights`.
+    pub fn new(lights: &[Light]) -> Self {
+        let mut uniforms: Self = bytemuck::Zeroable::zeroed();
+        for (raw, light) in uniforms.lights.iter_mut().zip(lights) {
+            *raw = light.to_raw();
+        }
+        uniforms.count = lights.len().min(MAX_LIGHTS) as u32;
+        uniforms
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graphics::color;
+
+    #[test]
+    fn directional_light_direction_is_normalized() {
+        let light = Light::Directional {
+            direction: cgmath::Vector3::new(0.0, 0.0, -2.0),
+            color: color::WHITE,
+        };
+        assert_eq!(light.to_raw().position, [0.0, 0.0, -1.0, 0.0]);
+    }
+
+    #[test]
+    fn zero_direction_shines_down() {
+        let light = Light::Directional {
+            direction: cgmath::Vector3::new(0.0, 0.0, 0.0),
+            color: color::WHITE,
+        };
+        assert_eq!(light.to_raw().position, [0.0, -1.0, 0.0, 0.0]);
+    }
+}
@@ -0,0 +1,75 @@
+use std::convert::TryFrom;
+use std::path::Path;
+
+use anyhow::Context;
+use cgmath::InnerSpace;
+
+use crate::graphics::{color, Vertex};
+
+/// Load every model in an OBJ file as a single mesh.
+///
+/// Faces are triangulated, and smooth normals are generated for models that don't have
+/// any. Vertices are white since OBJ files carry no vertex colors.
+pub fn load_obj(path: &Path) -> anyhow::Result<(Vec<Vertex>, Vec<u16>)> {
+    let (models, _materials) = tobj::load_obj(path, true)
+        .map_err(|e| anyhow::anyhow!("Failed to load {}: {}", path.display(), e))?;
+
+    let mut vertices = Vec::new();
+    let mut indices = Vec::new();
+
+    for model in models {
+        let mesh = model.mesh;
+        let first_vertex = vertices.len();
+
+        for i in 0..mesh.positions.len() / 3 {
+            let position = [mesh.positions[3 * i], mesh.positions[3 * i + 1], mesh.positions[3 * i + 2]];
+            let normal = if mesh.normals.is_empty() {
+                [0.0, 0.0, 0.0]
+            } else {
+                [mesh.normals[3 * i], mesh.normals[3 * i + 1], mesh.normals[3 * i + 2]]
+            };
+            // OBJ texture coordinates start at the bottom left, wgpu's at the top left
+            let tex_coords = if mesh.texcoords.is_empty() {
+                [0.0, 0.0]
+            } else {
+                [mesh.texcoords[2 * i], 1.0 - mesh.texcoords[2 * i + 1]]
+            };
+
+            vertices.push(Vertex::new(position, normal, tex_coords, color::WHITE));
+        }
+
+        if mesh.normals.is_empty() {
+            generate_normals(&mut vertices[first_vertex..], &mesh.indices);
+        }
+
+        for index in mesh.indices {
+            let index = u16::try_from(first_vertex + index as usize)
+                .with_context(|| format!("{} has too many vertices for 16 bit indices", path.display()))?;
+            indices.push(index);
+        }
+    }
+
+    Ok((vertices, indices))
+}
+
+/// Set each vertex normal to the area weighted average of the triangles sharing it.
+fn generate_normals(vertices: &mut [Vertex], indices: &[u32]) {
+    let mut normals = vec![cgmath::Vector3::new(0.0, 0.0, 0.0); vertices.len()];
+
+    for triangle in indices.chunks_exact(3) {
+        let [a, b, c] = [triangle[0] as usize, triangle[1] as usize, triangle[2] as usize];
+        let position = |i: usize| cgmath::Vector3::from(vertices[i].position);
+
+        // Unnormalized, so larger triangles contribute more
+        let face_normal = (position(b) - position(a)).cross(position(c) - position(a));
+        normals[a] += face_normal;
+        normals[b] += face_normal;
+        normals[c] += face_normal;
+    }
+
+    for (vertex, normal) in vertices.iter_mut().zip(normals) {
+        if normal.magnitude2() > 0.0 {
+            vertex.normal = normal.normalize().into();
+        }
+    }
+}
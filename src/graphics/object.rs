@@ -1,74 +1,416 @@
-use crate::graphics::{Vertex, Instance};
+use std::ops::Range;
+
+use crate::common::math::bounds::{Aabb, BoundingSphere};
+use crate::common::math::frustum::Frustum;
+use crate::graphics::{color, Color, Vertex, Instance, InstanceRaw, Layer, MaterialId, PipelineConfig};
+
+/// Smallest instance buffer allocated once an object gets its first instance.
+const MIN_INSTANCE_CAPACITY: usize = 16;
 
 pub struct Object {
+    // CPU side copies of the geometry, kept for scene snapshots
+    vertices: Vec<Vertex>,
+    indices: Option<Vec<u16>>,
     vertex_buffer: wgpu::Buffer,
-    index_buffer: wgpu::Buffer,
+    // None for objects drawn straight from the vertex buffer
+    index_buffer: Option<wgpu::Buffer>,
     instance_buffer: wgpu::Buffer,
+    bind_group: Option<wgpu::BindGroup>,
+    // Holds the material color as an RGBA vec4
+    material_buffer: wgpu::Buffer,
+    material_color: Option<Color>,
     instances: Vec<Instance>,
+    // Set when `instances` has changed since it was last uploaded
+    dirty: bool,
+    num_vertices: u32,
     num_indices: u32,
-    instance_buffer_size: usize,
+    instance_capacity: usize,
+    // Hard limit on `instances`, and so on the instance buffer's capacity
+    max_instances: Option<usize>,
+    // Only instances in this range are drawn, if set
+    instance_range: Option<Range<usize>>,
+    layer: Layer,
+    order: i32,
+    // Drawn with the overlay camera after everything else
+    screen_space: bool,
+    pipeline_config: PipelineConfig,
+    aabb: Aabb,
+    bounding_sphere: BoundingSphere,
 }
 
 impl Object {
-    pub fn new(device: &wgpu::Device, vertices: &[Vertex], indices: &[u16]) -> Self {
-        let vertex_buffer = device.create_buffer_with_data(
-            bytemuck::cast_slice(vertices),
-            wgpu::BufferUsage::VERTEX,
-        );
-        let index_buffer = device.create_buffer_with_data(
-            bytemuck::cast_slice(indices),
-            wgpu::BufferUsage::INDEX,
-        );
-        let num_indices = indices.len() as u32;
+    /// Create an object from its vertices, drawn through `indices` if there are any or
+    /// straight from the vertex buffer otherwise.
+    pub fn new(device: &wgpu::Device, vertices: &[Vertex], indices: Option<&[u16]>, pipeline_config: PipelineConfig) -> Self {
+        let (vertex_buffer, index_buffer) = create_geometry_buffers(device, vertices, indices);
+        let (aabb, bounding_sphere) = compute_bounds(vertices);
 
+        // Replaced by grow_instance_buffer once there are instances; until then one zeroed
+        // instance keeps it a whole InstanceRaw
+        let placeholder_instance: InstanceRaw = bytemuck::Zeroable::zeroed();
         let instance_buffer = device.create_buffer_with_data(
-            &[0, 1, 2],
+            bytemuck::cast_slice(&[placeholder_instance]),
             wgpu::BufferUsage::STORAGE,
         );
 
+        let material_buffer = device.create_buffer_with_data(
+            bytemuck::cast_slice(&material_data(color::WHITE)),
+            wgpu::BufferUsage::UNIFORM | wgpu::BufferUsage::COPY_DST,
+        );
+
         Self {
+            vertices: vertices.to_vec(),
+            indices: indices.map(<[u16]>::to_vec),
             vertex_buffer,
             index_buffer,
             instance_buffer,
+            bind_group: None,
+            material_buffer,
+            material_color: None,
             instances: Vec::new(),
-            num_indices,
-            instance_buffer_size: 0,
+            dirty: false,
+            num_vertices: vertices.len() as u32,
+            num_indices: indices.map_or(0, |indices| indices.len() as u32),
+            instance_capacity: 0,
+            max_instances: None,
+            instance_range: None,
+            layer: Layer::default(),
+            order: 0,
+            screen_space: false,
+            pipeline_config,
+            aabb,
+            bounding_sphere,
         }
     }
 
+    /// Replace the vertices and indices, reallocating their buffers. Instances, settings
+    /// and the instance buffer are kept.
+    pub fn set_geometry(&mut self, device: &wgpu::Device, vertices: &[Vertex], indices: Option<&[u16]>) {
+        let (vertex_buffer, index_buffer) = create_geometry_buffers(device, vertices, indices);
+        let (aabb, bounding_sphere) = compute_bounds(vertices);
+
+        self.vertices = vertices.to_vec();
+        self.indices = indices.map(<[u16]>::to_vec);
+        self.vertex_buffer = vertex_buffer;
+        self.index_buffer = index_buffer;
+        self.num_vertices = vertices.len() as u32;
+        self.num_indices = indices.map_or(0, |indices| indices.len() as u32);
+        self.aabb = aabb;
+        self.bounding_sphere = bounding_sphere;
+    }
+
+    pub fn layer(&self) -> Layer {
+        self.layer
+    }
+
+    pub fn set_layer(&mut self, layer: Layer) {
+        self.layer = layer;
+    }
+
+    /// Draw order within the object's layer; lower draws first, ties in creation order.
+    pub fn order(&self) -> i32 {
+        self.order
+    }
+
+    pub fn set_order(&mut self, order: i32) {
+        self.order = order;
+    }
+
+    pub fn is_screen_space(&self) -> bool {
+        self.screen_space
+    }
+
+    /// Screen space objects need a bind group using the overlay uniforms, so the caller
+    /// has to recreate it after changing this.
+    pub fn set_screen_space(&mut self, screen_space: bool) {
+        self.screen_space = screen_space;
+    }
+
+    pub fn pipeline_config(&self) -> PipelineConfig {
+        self.pipeline_config
+    }
+
+    /// The color the whole object is drawn with, or `None` when it uses its vertex colors.
+    pub fn material_color(&self) -> Option<Color> {
+        self.material_color
+    }
+
+    /// Draw the whole object in `color`, ignoring its vertex colors, or go back to the
+    /// vertex colors with `None`. This switches the object to a pipeline config with a
+    /// matching `material_color`, which the caller has to make sure exists.
+    pub fn set_material_color(&mut self, queue: &wgpu::Queue, color: Option<Color>) {
+        if let Some(color) = color {
+            queue.write_buffer(&self.material_buffer, 0, bytemuck::cast_slice(&material_data(color)));
+        }
+        self.material_color = color;
+        self.pipeline_config.material_color = color.is_some();
+    }
+
+    /// Draw with a custom material's shaders, or the built-in ones with `None`. Like
+    /// `set_material_color`, the caller has to make sure the new pipeline exists.
+    pub fn set_material(&mut self, material: Option<MaterialId>) {
+        self.pipeline_config.material = material;
+    }
+
+    pub fn material_buffer(&self) -> &wgpu::Buffer {
+        &self.material_buffer
+    }
+
+    /// Bounds of the vertices in model space.
+    pub fn aabb(&self) -> Aabb {
+        self.aabb
+    }
+
+    /// Bounds of every instance in world space, or `None` without any instances.
+    pub fn world_aabb(&self) -> Option<Aabb> {
+        self.instances
+            .iter()
+            .map(|instance| self.aabb.transformed(&instance.transform().matrix()))
+            .fold(None, |bounds: Option<Aabb>, aabb| match bounds {
+                Some(bounds) => Some(bounds.union(&aabb)),
+                None => Some(aabb),
+            })
+    }
+
+    /// Sphere containing every vertex, in model space.
+    pub fn bounding_sphere(&self) -> BoundingSphere {
+        self.bounding_sphere
+    }
+
+    /// The range of instances that gets drawn, or `None` to draw all of them.
+    pub fn instance_range(&self) -> Option<Range<usize>> {
+        self.instance_range.clone()
+    }
+
+    /// Limit drawing to the instances in `range`, or draw all of them again with `None`.
+    /// Indices past the last instance are ignored.
+    pub fn set_instance_range(&mut self, range: Option<Range<usize>>) {
+        self.instance_range = range;
+    }
+
+    /// Runs of consecutive instances within the instance range whose bounding sphere is at
+    /// least partly inside `frustum`.
+    pub fn visible_instance_ranges(&self, frustum: &Frustum) -> Vec<Range<usize>> {
+        use cgmath::Transform;
+
+        let len = self.instances.len();
+        let (start, end) = match &self.instance_range {
+            Some(range) => (range.start.min(len), range.end.min(len)),
+            None => (0, len),
+        };
+
+        let mut ranges: Vec<Range<usize>> = Vec::new();
+        for (index, instance) in self.instances.iter().enumerate().take(end).skip(start) {
+            let center = instance.transform().matrix().transform_point(self.bounding_sphere.center);
+            let scale = instance.scale.x.abs().max(instance.scale.y.abs()).max(instance.scale.z.abs());
+
+            if frustum.intersects_sphere(center, self.bounding_sphere.radius * scale) {
+                match ranges.last_mut() {
+                    Some(range) if range.end == index => range.end += 1,
+                    _ => ranges.push(index..index + 1),
+                }
+            }
+        }
+
+        ranges
+    }
+
+    pub fn vertices(&self) -> &[Vertex] {
+        &self.vertices
+    }
+
+    /// The indices, or `None` for an unindexed object.
+    pub fn indices(&self) -> Option<&[u16]> {
+        self.indices.as_deref()
+    }
+
     pub fn vertex_buffer(&self) -> &wgpu::Buffer {
         &self.vertex_buffer
     }
 
-    pub fn index_buffer(&self) -> &wgpu::Buffer {
-        &self.index_buffer
+    /// The index buffer, or `None` for an unindexed object.
+    pub fn index_buffer(&self) -> Option<&wgpu::Buffer> {
+        self.index_buffer.as_ref()
+    }
+
+    pub fn num_vertices(&self) -> u32 {
+        self.num_vertices
     }
 
     pub fn num_indices(&self) -> u32 {
         self.num_indices
     }
 
+    /// Triangles in one instance, or 0 for line and point topologies.
+    pub fn num_triangles(&self) -> u32 {
+        let count = match self.index_buffer {
+            Some(_) => self.num_indices,
+            None => self.num_vertices,
+        };
+        match self.pipeline_config.topology {
+            wgpu::PrimitiveTopology::TriangleList => count / 3,
+            wgpu::PrimitiveTopology::TriangleStrip => count.saturating_sub(2),
+            _ => 0,
+        }
+    }
+
     pub fn instance_buffer(&self) -> &wgpu::Buffer {
         &self.instance_buffer
     }
 
+    /// Size in bytes of the whole instance buffer, including unused capacity.
     pub fn instance_buffer_size(&self) -> usize {
-        self.instance_buffer_size
+        self.instance_capacity * std::mem::size_of::<InstanceRaw>()
+    }
+
+    /// Number of instances the GPU buffer can hold before it has to grow.
+    pub fn instance_capacity(&self) -> usize {
+        self.instance_capacity
+    }
+
+    /// The bind group used to draw this object, available once it has instances.
+    pub fn bind_group(&self) -> Option<&wgpu::BindGroup> {
+        self.bind_group.as_ref()
+    }
+
+    pub fn set_bind_group(&mut self, bind_group: wgpu::BindGroup) {
+        self.bind_group = Some(bind_group);
+    }
+
+    pub fn instances(&self) -> &[Instance] {
+        &self.instances
     }
 
     pub fn num_instances(&self) -> usize {
         self.instances.len()
     }
 
-    pub fn add_instance(&mut self, device: &wgpu::Device, position: cgmath::Vector3<f32>, rotation: cgmath::Quaternion<f32>) {
-        self.instances.push(Instance{ position, rotation });
+    /// Add an instance on the CPU side. It reaches the GPU on the next `upload_instances`.
+    ///
+    /// Returns `false` without adding it if the object already has `max_instances`.
+    pub fn add_instance(&mut self, instance: Instance) -> bool {
+        if self.max_instances.map_or(false, |max| self.instances.len() >= max) {
+            return false;
+        }
+
+        self.instances.push(instance);
+        self.dirty = true;
+        true
+    }
+
+    /// Most instances the object can have, or `None` for no limit.
+    pub fn max_instances(&self) -> Option<usize> {
+        self.max_instances
+    }
 
-        let instance_data = self.instances.iter().map(Instance::to_raw).collect::<Vec<_>>();
-        self.instance_buffer_size = instance_data.len() * std::mem::size_of::<cgmath::Matrix4<f32>>();
+    /// Limit the number of instances. Lowering it below the current count keeps the
+    /// existing instances but stops new ones being added.
+    pub fn set_max_instances(&mut self, max_instances: Option<usize>) {
+        self.max_instances = max_instances;
+    }
 
-        self.instance_buffer = device.create_buffer_with_data(
-            bytemuck::cast_slice(&instance_data),
-            wgpu::BufferUsage::STORAGE_READ,
-        );
+    /// Change an instance in place. It reaches the GPU on the next `upload_instances`.
+    pub fn update_instance(&mut self, instance_id: usize, update: impl FnOnce(&mut Instance)) -> bool {
+        match self.instances.get_mut(instance_id) {
+            Some(instance) => {
+                update(instance);
+                self.dirty = true;
+                true
+            }
+            None => false,
+        }
     }
+
+    /// Remove every instance, keeping the vertex, index and instance buffers for reuse.
+    pub fn clear_instances(&mut self) {
+        self.instances.clear();
+        // Nothing is drawn without instances, so there's nothing to upload either
+        self.dirty = false;
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// Upload all instances to the GPU if they changed since the last upload, tagged with
+    /// `object_id` for GPU picking.
+    ///
+    /// Returns `true` when the instance buffer had to be reallocated, in which case any
+    /// bind group referencing the old buffer must be recreated.
+    pub fn upload_instances(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, object_id: usize) -> bool {
+        if !self.dirty {
+            return false;
+        }
+        self.dirty = false;
+
+        let reallocated = self.instances.len() > self.instance_capacity;
+        if reallocated {
+            self.grow_instance_buffer(device);
+        }
+
+        let instance_data = self
+            .instances
+            .iter()
+            .map(|instance| {
+                let mut raw = instance.to_raw();
+                raw.object_id = object_id as u32 + 1;
+                raw
+            })
+            .collect::<Vec<_>>();
+        queue.write_buffer(&self.instance_buffer, 0, bytemuck::cast_slice(&instance_data));
+
+        reallocated
+    }
+
+    /// Allocate a new instance buffer with at least double the capacity, but no more than
+    /// `max_instances` needs.
+    fn grow_instance_buffer(&mut self, device: &wgpu::Device) {
+        let mut capacity = (self.instance_capacity * 2).max(MIN_INSTANCE_CAPACITY);
+        if let Some(max_instances) = self.max_instances {
+            capacity = capacity.min(max_instances);
+        }
+        let capacity = capacity.max(self.instances.len());
+
+        self.instance_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("instance_buffer"),
+            size: (capacity * std::mem::size_of::<InstanceRaw>()) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsage::STORAGE | wgpu::BufferUsage::COPY_DST,
+        });
+        self.instance_capacity = capacity;
+    }
+}
+
+/// The material uniform block in basic.frag.
+fn material_data(color: Color) -> [f32; 4] {
+    [color[0], color[1], color[2], 1.0]
+}
+
+fn create_geometry_buffers(
+    device: &wgpu::Device,
+    vertices: &[Vertex],
+    indices: Option<&[u16]>,
+) -> (wgpu::Buffer, Option<wgpu::Buffer>) {
+    let vertex_buffer = device.create_buffer_with_data(
+        bytemuck::cast_slice(vertices),
+        wgpu::BufferUsage::VERTEX,
+    );
+    let index_buffer = indices.map(|indices| {
+        device.create_buffer_with_data(
+            bytemuck::cast_slice(indices),
+            wgpu::BufferUsage::INDEX,
+        )
+    });
+
+    (vertex_buffer, index_buffer)
+}
+
+fn compute_bounds(vertices: &[Vertex]) -> (Aabb, BoundingSphere) {
+    // An object without vertices is treated as a point at the origin
+    let origin = cgmath::Point3::new(0.0, 0.0, 0.0);
+    let positions = vertices.iter().map(|vertex| cgmath::Point3::from(vertex.position));
+    let aabb = Aabb::from_points(positions.clone()).unwrap_or_else(|| Aabb::new(origin, origin));
+    let bounding_sphere = BoundingSphere::from_points(positions)
+        .unwrap_or_else(|| BoundingSphere::new(origin, 0.0));
+
+    (aabb, bounding_sphere)
 }
@@ -0,0 +1,99 @@
+use serde::{Deserialize, Serialize};
+
+/// Per-object render settings that are baked into a render pipeline.
+///
+/// `State` creates one pipeline per distinct config and shares it between objects.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct PipelineConfig {
+    /// Single-sided geometry like quads needs `CullMode::None` to be visible from behind.
+    pub cull_mode: wgpu::CullMode,
+    /// Which winding faces the camera. Meshes exported with clockwise winding need
+    /// `FrontFace::Cw` to not render inside out when culling back faces.
+    pub front_face: wgpu::FrontFace,
+    /// How vertices are assembled into primitives. `LineList` and `PointList` are most
+    /// useful with unindexed objects, for debug gizmos and particles.
+    pub topology: wgpu::PrimitiveTopology,
+    /// Turn fragment alpha into sample coverage, which smooths line edges and cutouts.
+    /// Does nothing unless `GraphicsConfig::sample_count` is above 1. Never used for
+    /// picking.
+    pub alpha_to_coverage: bool,
+    /// Bit mask of the samples fragments may write, `!0` for all of them. With the default
+    /// `GraphicsConfig::sample_count` of 1 only the lowest bit matters, so any odd mask
+    /// draws normally and any even mask draws nothing.
+    pub sample_mask: u32,
+    pub depth_bias: DepthBias,
+    /// Color the whole object with its material color instead of its vertex colors. Set
+    /// along with the color by `State::set_object_material_color`. Custom materials read
+    /// the color themselves, so this only affects the built-in shaders.
+    pub material_color: bool,
+    /// Custom shaders from `State::create_material`, or `None` for the built-in ones.
+    pub material: Option<MaterialId>,
+}
+
+/// A pair of custom shaders created by `State::create_material`. Only valid for the
+/// `State` that created it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct MaterialId(pub(super) usize);
+
+impl PipelineConfig {
+    /// Settings for gizmo and debug lines: a line list, never culled, with alpha to
+    /// coverage for smoother edges when `GraphicsConfig::sample_count` is above 1.
+    pub fn lines() -> Self {
+        Self {
+            cull_mode: wgpu::CullMode::None,
+            front_face: wgpu::FrontFace::Ccw,
+            topology: wgpu::PrimitiveTopology::LineList,
+            alpha_to_coverage: true,
+            sample_mask: !0,
+            depth_bias: DepthBias::default(),
+            material_color: false,
+            material: None,
+        }
+    }
+}
+
+impl Default for PipelineConfig {
+    fn default() -> Self {
+        Self {
+            cull_mode: wgpu::CullMode::Back,
+            front_face: wgpu::FrontFace::Ccw,
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            alpha_to_coverage: false,
+            sample_mask: !0,
+            depth_bias: DepthBias::default(),
+            material_color: false,
+            material: None,
+        }
+    }
+}
+
+/// Depth offset applied to every fragment, to push coplanar geometry like wireframes and
+/// decals toward the camera so it doesn't z-fight. Only matters with depth testing.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct DepthBias {
+    /// Constant offset, in units of the smallest depth difference the depth format can hold.
+    pub constant: i32,
+    /// Offset scaled by the polygon's depth slope, for surfaces at grazing angles.
+    pub slope_scale: f32,
+    /// Largest offset applied, or `0.0` for no limit.
+    pub clamp: f32,
+}
+
+// Compared bitwise so the floats can be part of the pipeline cache key
+impl PartialEq for DepthBias {
+    fn eq(&self, other: &Self) -> bool {
+        self.constant == other.constant
+            && self.slope_scale.to_bits() == other.slope_scale.to_bits()
+            && self.clamp.to_bits() == other.clamp.to_bits()
+    }
+}
+
+impl Eq for DepthBias {}
+
+impl std::hash::Hash for DepthBias {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.constant.hash(state);
+        self.slope_scale.to_bits().hash(state);
+        self.clamp.to_bits().hash(state);
+    }
+}
@@ -0,0 +1,152 @@
+use cgmath::Vector3;
+
+use crate::graphics::{color, Vertex};
+
+/// A unit square on the XY plane, centered on the origin and facing +Z.
+pub fn quad() -> (Vec<Vertex>, Vec<u16>) {
+    let mut vertices = Vec::with_capacity(4);
+    let mut indices = Vec::with_capacity(6);
+
+    push_face(
+        &mut vertices,
+        &mut indices,
+        Vector3::new(0.0, 0.0, 0.0),
+        Vector3::new(0.5, 0.0, 0.0),
+        Vector3::new(0.0, 0.5, 0.0),
+    );
+
+    (vertices, indices)
+}
+
+/// A `size` by `size` square on the XZ plane, centered on the origin and facing +Y.
+pub fn plane(size: f32) -> (Vec<Vertex>, Vec<u16>) {
+    let half = size / 2.0;
+    let mut vertices = Vec::with_capacity(4);
+    let mut indices = Vec::with_capacity(6);
+
+    push_face(
+        &mut vertices,
+        &mut indices,
+        Vector3::new(0.0, 0.0, 0.0),
+        Vector3::new(half, 0.0, 0.0),
+        Vector3::new(0.0, 0.0, -half),
+    );
+
+    (vertices, indices)
+}
+
+/// A cube with sides of length `size`, centered on the origin.
+///
+/// Each face has its own vertices so normals and texture coordinates stay per face.
+pub fn cube(size: f32) -> (Vec<Vertex>, Vec<u16>) {
+    let half = size / 2.0;
+    let mut vertices = Vec::with_capacity(24);
+    let mut indices = Vec::with_capacity(36);
+
+    // (normal, u, v) with u x v == normal so every face winds counter-clockwise
+    #[rustfmt::skip]
+    let faces = [
+        (Vector3::unit_x(), -Vector3::unit_z(), Vector3::unit_y()),
+        (-Vector3::unit_x(), Vector3::unit_z(), Vector3::unit_y()),
+        (Vector3::unit_y(), Vector3::unit_x(), -Vector3::unit_z()),
+        (-Vector3::unit_y(), Vector3::unit_x(), Vector3::unit_z()),
+        (Vector3::unit_z(), Vector3::unit_x(), Vector3::unit_y()),
+        (-Vector3::unit_z(), -Vector3::unit_x(), Vector3::unit_y()),
+    ];
+
+    for &(normal, u, v) in &faces {
+        push_face(&mut vertices, &mut indices, normal * half, u * half, v * half);
+    }
+
+    (vertices, indices)
+}
+
+/// A sphere made of `sectors` slices around the Y axis and `stacks` bands from pole to pole.
+///
+/// Texture coordinates wrap once around the equator, so the seam gets its own column of
+/// vertices. Panics if the sphere would need more vertices than 16 bit indices can address.
+pub fn uv_sphere(radius: f32, sectors: u16, stacks: u16) -> (Vec<Vertex>, Vec<u16>) {
+    use std::f32::consts::PI;
+
+    // Any less and the sphere collapses into a flat shape
+    let sectors = sectors.max(3);
+    let stacks = stacks.max(2);
+
+    let ring_size = sectors as u32 + 1;
+    let vertex_count = ring_size * (stacks as u32 + 1);
+    assert!(
+        vertex_count <= u16::MAX as u32 + 1,
+        "uv_sphere with {} sectors and {} stacks needs too many vertices",
+        sectors,
+        stacks
+    );
+
+    let mut vertices = Vec::with_capacity(vertex_count as usize);
+    let mut indices = Vec::with_capacity(sectors as usize * stacks as usize * 6);
+
+    for stack in 0..=stacks {
+        // From the north pole down to the south pole
+        let phi = PI / 2.0 - stack as f32 * PI / stacks as f32;
+        let ring_radius = phi.cos();
+        let y = phi.sin();
+
+        for sector in 0..=sectors {
+            let theta = sector as f32 * 2.0 * PI / sectors as f32;
+            let normal = [ring_radius * theta.sin(), y, ring_radius * theta.cos()];
+            let position = [normal[0] * radius, normal[1] * radius, normal[2] * radius];
+            let tex_coords = [sector as f32 / sectors as f32, stack as f32 / stacks as f32];
+
+            vertices.push(Vertex::new(position, normal, tex_coords, color::WHITE));
+        }
+    }
+
+    let ring_size = ring_size as u16;
+    for stack in 0..stacks {
+        for sector in 0..sectors {
+            let top = stack * ring_size + sector;
+            let bottom = top + ring_size;
+
+            // The triangles touching the poles would be degenerate
+            if stack != 0 {
+                indices.extend_from_slice(&[top, bottom, top + 1]);
+            }
+            if stack != stacks - 1 {
+                indices.extend_from_slice(&[top + 1, bottom, bottom + 1]);
+            }
+        }
+    }
+
+    (vertices, indices)
+}
+
+/// Append a square face spanning `center - u - v` to `center + u + v`, facing `u x v`.
+fn push_face(
+    vertices: &mut Vec<Vertex>,
+    indices: &mut Vec<u16>,
+    center: Vector3<f32>,
+    u: Vector3<f32>,
+    v: Vector3<f32>,
+) {
+    use cgmath::InnerSpace;
+
+    let normal = u.cross(v).normalize().into();
+    let first = vertices.len() as u16;
+
+    #[rustfmt::skip]
+    let corners = [
+        (center - u - v, [0.0, 1.0]),
+        (center + u - v, [1.0, 1.0]),
+        (center + u + v, [1.0, 0.0]),
+        (center - u + v, [0.0, 0.0]),
+    ];
+
+    for &(position, tex_coords) in &corners {
+        vertices.push(Vertex::new(position.into(), normal, tex_coords, color::WHITE));
+    }
+
+    #[rustfmt::skip]
+    indices.extend_from_slice(&[
+        first, first + 1, first + 2,
+        first, first + 2, first + 3,
+    ]);
+}
@@ -0,0 +1,15 @@
+/// Everything a render callback needs to draw into the current frame.
+///
+/// Passed to the callback registered with `State::set_render_callback`, which runs after
+/// every layer has been drawn and before the frame is submitted. Render passes begun on
+/// `encoder` should load rather than clear `view` to draw on top of the scene.
+pub struct RenderContext<'a> {
+    pub device: &'a wgpu::Device,
+    pub queue: &'a wgpu::Queue,
+    pub encoder: &'a mut wgpu::CommandEncoder,
+    pub view: &'a wgpu::TextureView,
+    pub format: wgpu::TextureFormat,
+    pub size: winit::dpi::PhysicalSize<u32>,
+}
+
+pub type RenderCallback = Box<dyn FnMut(&mut RenderContext)>;
@@ -0,0 +1,51 @@
+use futures::executor::block_on;
+use winit::{
+    event::*,
+    event_loop::{ControlFlow, EventLoop},
+    window::WindowBuilder,
+};
+
+use crate::graphics::{GraphicsConfig, State};
+
+/// Open a window and drive the event loop, never returning unless setup fails.
+///
+/// `window` sets the window's title, size and so on. `setup` runs once after `State` is
+/// created, to create objects and instances, and `update` runs before every frame is
+/// rendered. Events are handled by `State::handle_event`, and escape exits too.
+pub fn run(
+    window: WindowBuilder,
+    config: GraphicsConfig,
+    setup: impl FnOnce(&mut State),
+    mut update: impl FnMut(&mut State) + 'static,
+) -> anyhow::Result<()> {
+    let event_loop = EventLoop::new();
+    let window = window.build(&event_loop)?;
+
+    let mut state = block_on(State::new(&window, config))?;
+    setup(&mut state);
+
+    event_loop.run(move |event, _, control_flow| {
+        match &event {
+            Event::WindowEvent {
+                event:
+                    WindowEvent::KeyboardInput {
+                        input:
+                            KeyboardInput {
+                                state: ElementState::Pressed,
+                                virtual_keycode: Some(VirtualKeyCode::Escape),
+                                ..
+                            },
+                        ..
+                    },
+                ..
+            } => {
+                *control_flow = ControlFlow::Exit;
+                return;
+            }
+            Event::RedrawRequested(_) => update(&mut state),
+            _ => {}
+        }
+
+        *control_flow = state.handle_event(&event, &window);
+    })
+}
@@ -0,0 +1,63 @@
+use serde::{Deserialize, Serialize};
+
+use crate::graphics::{Color, Instance, Layer, PipelineConfig, Vertex};
+
+/// Everything needed to recreate a scene, without any GPU resources.
+///
+/// Created by `State::scene_snapshot` and loaded with `State::load_scene`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SceneData {
+    /// Objects in creation order.
+    pub objects: Vec<ObjectData>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ObjectData {
+    pub vertices: Vec<Vertex>,
+    /// `None` for unindexed objects.
+    pub indices: Option<Vec<u16>>,
+    pub layer: Layer,
+    #[serde(default)]
+    pub order: i32,
+    #[serde(default)]
+    pub screen_space: bool,
+    /// `None` when the object uses its vertex colors.
+    #[serde(default)]
+    pub material_color: Option<Color>,
+    pub pipeline_config: PipelineConfig,
+    pub instances: Vec<InstanceData>,
+}
+
+/// An `Instance` as plain arrays, since cgmath's types aren't serializable.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct InstanceData {
+    pub position: [f32; 3],
+    /// Quaternion as `[x, y, z, w]`.
+    pub rotation: [f32; 4],
+    pub scale: [f32; 3],
+    pub color: [f32; 4],
+}
+
+impl From<&Instance> for InstanceData {
+    fn from(instance: &Instance) -> Self {
+        let rotation = instance.rotation;
+        Self {
+            position: instance.position.into(),
+            rotation: [rotation.v.x, rotation.v.y, rotation.v.z, rotation.s],
+            scale: instance.scale.into(),
+            color: instance.color,
+        }
+    }
+}
+
+impl From<InstanceData> for Instance {
+    fn from(data: InstanceData) -> Self {
+        let [x, y, z, w] = data.rotation;
+        Self {
+            position: data.position.into(),
+            rotation: cgmath::Quaternion::new(w, x, y, z),
+            scale: data.scale.into(),
+            color: data.color,
+        }
+    }
+}
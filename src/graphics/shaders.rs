@@ -1,17 +1,42 @@
 pub mod basic;
+pub mod pick;
+pub mod reflect;
 
 use anyhow::Context;
 use wgpu::ShaderModuleSource;
 
+// `spirv(name, defines)`, the built-in shader variants build.rs compiled
+#[cfg(feature = "precompiled-shaders")]
+mod precompiled {
+    include!(concat!(env!("OUT_DIR"), "/precompiled_shaders.rs"));
+}
+
+/// Compiles GLSL to SPIR-V with shaderc.
+///
+/// shaderc is only initialized by the first compile, so with the `precompiled-shaders`
+/// feature the built-in shaders work without it.
+#[derive(Default)]
 pub struct ShaderCompiler {
-    compiler: shaderc::Compiler,
+    compiler: Option<shaderc::Compiler>,
 }
 
 impl ShaderCompiler {
-    pub fn new() -> anyhow::Result<Self> {
-        let compiler = shaderc::Compiler::new().context("Failed to create shader compiler")?;
+    pub fn new() -> Self {
+        Self::default()
+    }
 
-        Ok(Self { compiler })
+    /// The shaderc compiler, initialized on first use with an explanation if that fails.
+    fn compiler(&mut self) -> anyhow::Result<&mut shaderc::Compiler> {
+        if self.compiler.is_none() {
+            let compiler = shaderc::Compiler::new().context(
+                "Failed to initialize the shaderc shader compiler. shaderc and its shared library \
+                 (libshaderc_shared) must be installed to compile shaders at runtime, or build \
+                 with the precompiled-shaders feature to use the built-in shaders without it",
+            )?;
+            self.compiler = Some(compiler);
+        }
+
+        Ok(self.compiler.as_mut().unwrap())
     }
 
     pub fn create_fragment_shader(
@@ -20,7 +45,18 @@ impl ShaderCompiler {
         name: impl AsRef<str>,
         entry_point: impl AsRef<str>,
     ) -> anyhow::Result<ShaderModuleSource> {
-        self.create_shader(source, name, entry_point, shaderc::ShaderKind::Fragment)
+        self.create_shader(source, name, entry_point, shaderc::ShaderKind::Fragment, &[])
+    }
+
+    /// Compile a variant of a fragment shader with each of `defines` defined, for `#ifdef`.
+    pub fn create_fragment_shader_with_defines(
+        &mut self,
+        source: impl AsRef<str>,
+        name: impl AsRef<str>,
+        entry_point: impl AsRef<str>,
+        defines: &[&str],
+    ) -> anyhow::Result<ShaderModuleSource> {
+        self.create_shader(source, name, entry_point, shaderc::ShaderKind::Fragment, defines)
     }
 
     pub fn create_vertex_shader(
@@ -29,7 +65,7 @@ impl ShaderCompiler {
         name: impl AsRef<str>,
         entry_point: impl AsRef<str>,
     ) -> anyhow::Result<ShaderModuleSource> {
-        self.create_shader(source, name, entry_point, shaderc::ShaderKind::Vertex)
+        self.create_shader(source, name, entry_point, shaderc::ShaderKind::Vertex, &[])
     }
 
     pub fn create_compute_shader(
@@ -38,7 +74,35 @@ impl ShaderCompiler {
         name: impl AsRef<str>,
         entry_point: impl AsRef<str>,
     ) -> anyhow::Result<ShaderModuleSource> {
-        self.create_shader(source, name, entry_point, shaderc::ShaderKind::Compute)
+        self.create_shader(source, name, entry_point, shaderc::ShaderKind::Compute, &[])
+    }
+
+    /// Compile a built-in shader with `defines`, or with the `precompiled-shaders` feature
+    /// use the SPIR-V build.rs compiled for it. build.rs has to list every variant used.
+    ///
+    /// Debug builds check the shader's layouts against the Rust types with `reflect`.
+    #[cfg_attr(feature = "precompiled-shaders", allow(unused_variables))]
+    fn builtin_shader(
+        &mut self,
+        source: &str,
+        name: &str,
+        kind: shaderc::ShaderKind,
+        defines: &[&str],
+    ) -> anyhow::Result<ShaderModuleSource> {
+        #[cfg(feature = "precompiled-shaders")]
+        let data = {
+            let spirv = precompiled::spirv(name, defines)
+                .with_context(|| format!("{} with {:?} wasn't precompiled", name, defines))?;
+            wgpu::util::make_spirv(spirv)
+        };
+        #[cfg(not(feature = "precompiled-shaders"))]
+        let data = self.create_shader(source, name, "main", kind, defines)?;
+
+        if cfg!(debug_assertions) {
+            check_layouts(name, &data);
+        }
+
+        Ok(data)
     }
 
     fn create_shader(
@@ -47,16 +111,36 @@ impl ShaderCompiler {
         name: impl AsRef<str>,
         entry_point: impl AsRef<str>,
         kind: shaderc::ShaderKind,
+        defines: &[&str],
     ) -> anyhow::Result<ShaderModuleSource> {
-        let spirv = self.compiler.compile_into_spirv(
+        let options = if defines.is_empty() {
+            None
+        } else {
+            let mut options = shaderc::CompileOptions::new().context("Failed to create shader compile options")?;
+            for define in defines {
+                options.add_macro_definition(define, None);
+            }
+            Some(options)
+        };
+
+        let spirv = self.compiler()?.compile_into_spirv(
             source.as_ref(),
             kind,
             name.as_ref(),
             entry_point.as_ref(),
-            None,
-        )?;
+            options.as_ref(),
+        )
+        .with_context(|| format!("Failed to compile {}", name.as_ref()))?;
         let data = wgpu::util::make_spirv(spirv.as_binary_u8());
 
         Ok(data)
     }
 }
+
+/// Log any mismatches between a shader and the Rust types `State` feeds it.
+pub fn check_layouts(name: &str, data: &ShaderModuleSource) -> bool {
+    match data {
+        ShaderModuleSource::SpirV(spirv) => reflect::check_layouts(name, spirv),
+        _ => true,
+    }
+}
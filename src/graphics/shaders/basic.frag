@@ -1,8 +1,83 @@
 #version 450
 
 layout(location=0) in vec3 v_color;
+layout(location=1) in vec4 v_tint;
+layout(location=2) in vec3 v_position;
+layout(location=3) in vec3 v_normal;
 layout(location=0) out vec4 f_color;
 
+// Must match MAX_LIGHTS and the light kinds in light.rs
+const uint MAX_LIGHTS = 8u;
+const uint LIGHT_AMBIENT = 0u;
+const uint LIGHT_DIRECTIONAL = 1u;
+const uint LIGHT_POINT = 2u;
+
+struct Light {
+    // Direction for directional lights
+    vec4 position;
+    vec3 color;
+    uint kind;
+    vec4 attenuation;
+};
+
+layout(set=0, binding=2)
+uniform Lights {
+    Light u_lights[MAX_LIGHTS];
+    uint u_light_count;
+};
+
+#ifdef MATERIAL_COLOR
+layout(set=0, binding=3)
+uniform Material {
+    vec4 u_base_color;
+};
+#endif
+
+#ifdef SRGB_INPUT
+// The exact sRGB transfer function, not a 2.2 gamma approximation
+vec3 srgb_to_linear(vec3 color) {
+    vec3 low = color / 12.92;
+    vec3 high = pow((color + 0.055) / 1.055, vec3(2.4));
+    return mix(high, low, lessThanEqual(color, vec3(0.04045)));
+}
+#endif
+
+vec3 lighting() {
+    // Unlit without lights, and for geometry without normals like debug lines
+    if (u_light_count == 0u || dot(v_normal, v_normal) == 0.0) {
+        return vec3(1.0);
+    }
+
+    vec3 normal = normalize(v_normal);
+    vec3 total = vec3(0.0);
+    for (uint i = 0u; i < min(u_light_count, MAX_LIGHTS); i++) {
+        Light light = u_lights[i];
+        if (light.kind == LIGHT_AMBIENT) {
+            total += light.color;
+        } else if (light.kind == LIGHT_DIRECTIONAL) {
+            total += light.color * max(dot(normal, -light.position.xyz), 0.0);
+        } else if (light.kind == LIGHT_POINT) {
+            vec3 to_light = light.position.xyz - v_position;
+            float distance = length(to_light);
+            float attenuation = light.attenuation.x
+                + light.attenuation.y * distance
+                + light.attenuation.z * distance * distance;
+            total += light.color * max(dot(normal, to_light / distance), 0.0) / attenuation;
+        }
+    }
+    return total;
+}
+
 void main() {
-    f_color = vec4(v_color, 1.0);
+#ifdef MATERIAL_COLOR
+    vec3 base_color = u_base_color.rgb;
+#else
+    vec3 base_color = v_color;
+#endif
+    vec4 tint = v_tint;
+#ifdef SRGB_INPUT
+    base_color = srgb_to_linear(base_color);
+    tint.rgb = srgb_to_linear(tint.rgb);
+#endif
+    f_color = vec4(base_color * lighting(), 1.0) * tint;
 }
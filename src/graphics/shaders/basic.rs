@@ -2,12 +2,31 @@ use wgpu::{Device, ShaderModule};
 
 use super::ShaderCompiler;
 
+/// The fragment shader, converting colors from sRGB to linear first if `srgb_input`.
 pub fn fragment_module(
     device: &Device,
     compiler: &mut ShaderCompiler,
+    srgb_input: bool,
 ) -> anyhow::Result<ShaderModule> {
-    let fs_src = include_str!("basic.frag");
-    let fs_data = compiler.create_fragment_shader(fs_src, "basic.frag", "main")?;
+    let defines: &[&str] = if srgb_input { &["SRGB_INPUT"] } else { &[] };
+    let fs_data = compiler.builtin_shader(include_str!("basic.frag"), "basic.frag", shaderc::ShaderKind::Fragment, defines)?;
+    Ok(device.create_shader_module(fs_data))
+}
+
+/// The fragment shader variant that colors objects with their material color instead of
+/// their vertex colors, read from the material uniform buffer.
+pub fn material_fragment_module(
+    device: &Device,
+    compiler: &mut ShaderCompiler,
+    srgb_input: bool,
+) -> anyhow::Result<ShaderModule> {
+    // In the order build.rs lists them
+    let mut defines = vec!["MATERIAL_COLOR"];
+    if srgb_input {
+        defines.push("SRGB_INPUT");
+    }
+
+    let fs_data = compiler.builtin_shader(include_str!("basic.frag"), "basic.frag", shaderc::ShaderKind::Fragment, &defines)?;
     Ok(device.create_shader_module(fs_data))
 }
 
@@ -15,7 +34,6 @@ pub fn vertex_module(
     device: &Device,
     compiler: &mut ShaderCompiler,
 ) -> anyhow::Result<ShaderModule> {
-    let vs_src = include_str!("basic.vert");
-    let vs_data = compiler.create_vertex_shader(vs_src, "basic.vert", "main")?;
+    let vs_data = compiler.builtin_shader(include_str!("basic.vert"), "basic.vert", shaderc::ShaderKind::Vertex, &[])?;
     Ok(device.create_shader_module(vs_data))
 }
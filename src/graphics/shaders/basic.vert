@@ -2,20 +2,38 @@
 
 layout(location=0) in vec3 a_position;
 layout(location=1) in vec3 a_color;
+layout(location=2) in vec3 a_normal;
+layout(location=3) in vec2 a_tex_coords;
 
 layout(location=0) out vec3 v_color;
+layout(location=1) out vec4 v_tint;
+layout(location=2) out vec3 v_position;
+layout(location=3) out vec3 v_normal;
 
 layout(set=0, binding=0)
 uniform Uniforms {
     mat4 u_view_proj;
 };
 
+struct Instance {
+    mat4 model;
+    vec4 color;
+    uint object_id;
+};
+
 layout(set=0, binding=1) 
 buffer Instances {
-    mat4 s_models[];
+    Instance s_instances[];
 };
 
 void main() {
+    Instance instance = s_instances[gl_InstanceIndex];
     v_color = a_color;
-    gl_Position = u_view_proj * s_models[gl_InstanceIndex] * vec4(a_position, 1.0);
+    v_tint = instance.color;
+
+    vec4 world_position = instance.model * vec4(a_position, 1.0);
+    v_position = world_position.xyz;
+    // The inverse transpose keeps normals perpendicular under non-uniform scale
+    v_normal = mat3(transpose(inverse(instance.model))) * a_normal;
+    gl_Position = u_view_proj * world_position;
 }
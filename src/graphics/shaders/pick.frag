@@ -0,0 +1,8 @@
+#version 450
+
+layout(location=0) flat in uvec2 v_id;
+layout(location=0) out uvec2 f_id;
+
+void main() {
+    f_id = v_id;
+}
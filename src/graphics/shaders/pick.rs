@@ -0,0 +1,19 @@
+use wgpu::{Device, ShaderModule};
+
+use super::ShaderCompiler;
+
+pub fn fragment_module(
+    device: &Device,
+    compiler: &mut ShaderCompiler,
+) -> anyhow::Result<ShaderModule> {
+    let fs_data = compiler.builtin_shader(include_str!("pick.frag"), "pick.frag", shaderc::ShaderKind::Fragment, &[])?;
+    Ok(device.create_shader_module(fs_data))
+}
+
+pub fn vertex_module(
+    device: &Device,
+    compiler: &mut ShaderCompiler,
+) -> anyhow::Result<ShaderModule> {
+    let vs_data = compiler.builtin_shader(include_str!("pick.vert"), "pick.vert", shaderc::ShaderKind::Vertex, &[])?;
+    Ok(device.create_shader_module(vs_data))
+}
@@ -0,0 +1,30 @@
+#version 450
+
+layout(location=0) in vec3 a_position;
+layout(location=1) in vec3 a_color;
+layout(location=2) in vec3 a_normal;
+layout(location=3) in vec2 a_tex_coords;
+
+layout(location=0) flat out uvec2 v_id;
+
+layout(set=0, binding=0)
+uniform Uniforms {
+    mat4 u_view_proj;
+};
+
+struct Instance {
+    mat4 model;
+    vec4 color;
+    uint object_id;
+};
+
+layout(set=0, binding=1) 
+buffer Instances {
+    Instance s_instances[];
+};
+
+void main() {
+    Instance instance = s_instances[gl_InstanceIndex];
+    v_id = uvec2(instance.object_id, gl_InstanceIndex);
+    gl_Position = u_view_proj * instance.model * vec4(a_position, 1.0);
+}
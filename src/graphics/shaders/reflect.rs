@@ -0,0 +1,602 @@
+//! Checks compiled shaders against the Rust types that feed them, so a field added to
+//! `Vertex` or a uniform struct without updating the GLSL is reported instead of rendering
+//! garbage.
+//!
+//! This reads just enough SPIR-V to find the vertex inputs and buffer blocks. naga 0.2, the
+//! version wgpu uses, rejects the `gl_PerVertex` block glslang writes for vertex shaders.
+
+use std::collections::HashMap;
+
+use crate::graphics::{InstanceRaw, LightUniforms, Uniforms, Vertex};
+
+const MAGIC: u32 = 0x0723_0203;
+
+const OP_ENTRY_POINT: u32 = 15;
+const OP_TYPE_BOOL: u32 = 20;
+const OP_TYPE_INT: u32 = 21;
+const OP_TYPE_FLOAT: u32 = 22;
+const OP_TYPE_VECTOR: u32 = 23;
+const OP_TYPE_MATRIX: u32 = 24;
+const OP_TYPE_ARRAY: u32 = 28;
+const OP_TYPE_RUNTIME_ARRAY: u32 = 29;
+const OP_TYPE_STRUCT: u32 = 30;
+const OP_TYPE_POINTER: u32 = 32;
+const OP_CONSTANT: u32 = 43;
+const OP_VARIABLE: u32 = 59;
+const OP_DECORATE: u32 = 71;
+const OP_MEMBER_DECORATE: u32 = 72;
+
+const EXECUTION_MODEL_VERTEX: u32 = 0;
+
+// Far deeper than any real shader's types nest, so only malformed modules reach it
+const MAX_TYPE_DEPTH: u32 = 64;
+
+const DECORATION_ARRAY_STRIDE: u32 = 6;
+const DECORATION_LOCATION: u32 = 30;
+const DECORATION_BINDING: u32 = 33;
+const DECORATION_DESCRIPTOR_SET: u32 = 34;
+const DECORATION_OFFSET: u32 = 35;
+
+const STORAGE_CLASS_INPUT: u32 = 1;
+const STORAGE_CLASS_UNIFORM: u32 = 2;
+const STORAGE_CLASS_PUSH_CONSTANT: u32 = 9;
+const STORAGE_CLASS_STORAGE_BUFFER: u32 = 12;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ScalarKind {
+    Bool,
+    Sint,
+    Uint,
+    Float,
+}
+
+#[derive(Debug)]
+enum Type {
+    Scalar { kind: ScalarKind, width: u32 },
+    Vector { component: u32, count: u32 },
+    Matrix { column: u32, count: u32 },
+    // `length` is the id of a constant
+    Array { element: u32, length: u32 },
+    RuntimeArray,
+    Struct { members: Vec<u32> },
+    Pointer { pointee: u32 },
+}
+
+struct Variable {
+    id: u32,
+    pointer_type: u32,
+    storage_class: u32,
+}
+
+/// The parts of a SPIR-V module the checks need, by result id.
+#[derive(Default)]
+struct Reflection {
+    execution_model: Option<u32>,
+    types: HashMap<u32, Type>,
+    constants: HashMap<u32, u32>,
+    // First literal of each (id, decoration)
+    decorations: HashMap<(u32, u32), u32>,
+    member_offsets: HashMap<(u32, u32), u32>,
+    variables: Vec<Variable>,
+}
+
+impl Reflection {
+    fn parse(spirv: &[u32]) -> Result<Self, &'static str> {
+        if spirv.len() < 5 || spirv[0] != MAGIC {
+            return Err("not SPIR-V");
+        }
+
+        let mut reflection = Self::default();
+        let mut words = &spirv[5..];
+        while !words.is_empty() {
+            let count = (words[0] >> 16) as usize;
+            if count == 0 || count > words.len() {
+                return Err("truncated instruction");
+            }
+
+            let opcode = words[0] & 0xffff;
+            match (opcode, &words[1..count]) {
+                (OP_ENTRY_POINT, [model, ..]) => {
+                    reflection.execution_model.get_or_insert(*model);
+                }
+                (OP_TYPE_BOOL, [id]) => {
+                    reflection.add_type(*id, Type::Scalar { kind: ScalarKind::Bool, width: 32 });
+                }
+                (OP_TYPE_INT, [id, width, signed]) => {
+                    let kind = if *signed == 0 { ScalarKind::Uint } else { ScalarKind::Sint };
+                    reflection.add_type(*id, Type::Scalar { kind, width: *width });
+                }
+                (OP_TYPE_FLOAT, [id, width]) => {
+                    reflection.add_type(*id, Type::Scalar { kind: ScalarKind::Float, width: *width });
+                }
+                (OP_TYPE_VECTOR, [id, component, count]) => {
+                    reflection.add_type(*id, Type::Vector { component: *component, count: *count });
+                }
+                (OP_TYPE_MATRIX, [id, column, count]) => {
+                    reflection.add_type(*id, Type::Matrix { column: *column, count: *count });
+                }
+                (OP_TYPE_ARRAY, [id, element, length]) => {
+                    reflection.add_type(*id, Type::Array { element: *element, length: *length });
+                }
+                (OP_TYPE_RUNTIME_ARRAY, [id, _]) => {
+                    reflection.add_type(*id, Type::RuntimeArray);
+                }
+                (OP_TYPE_STRUCT, [id, members @ ..]) => {
+                    reflection.add_type(*id, Type::Struct { members: members.to_vec() });
+                }
+                (OP_TYPE_POINTER, [id, _, pointee]) => {
+                    reflection.add_type(*id, Type::Pointer { pointee: *pointee });
+                }
+                // Only 32 bit constants are needed, for array lengths
+                (OP_CONSTANT, [_, id, value]) => {
+                    reflection.constants.insert(*id, *value);
+                }
+                (OP_VARIABLE, [pointer_type, id, storage_class, ..]) => reflection.variables.push(Variable {
+                    id: *id,
+                    pointer_type: *pointer_type,
+                    storage_class: *storage_class,
+                }),
+                (OP_DECORATE, [id, decoration, value, ..]) => {
+                    reflection.decorations.insert((*id, *decoration), *value);
+                }
+                (OP_MEMBER_DECORATE, [id, member, DECORATION_OFFSET, offset]) => {
+                    reflection.member_offsets.insert((*id, *member), *offset);
+                }
+                _ => {}
+            }
+
+            words = &words[count..];
+        }
+
+        Ok(reflection)
+    }
+
+    fn add_type(&mut self, id: u32, ty: Type) {
+        self.types.insert(id, ty);
+    }
+
+    fn pointee(&self, pointer_type: u32) -> Option<u32> {
+        match self.types.get(&pointer_type)? {
+            Type::Pointer { pointee } => Some(*pointee),
+            _ => None,
+        }
+    }
+
+    /// The scalar kind and component count of a scalar or vector type.
+    fn components(&self, id: u32) -> Option<(ScalarKind, u32)> {
+        match self.types.get(&id)? {
+            Type::Scalar { kind, .. } => Some((*kind, 1)),
+            // Vector components are always scalars, so there's nothing to recurse into
+            Type::Vector { component, count } => match self.types.get(component)? {
+                Type::Scalar { kind, .. } => Some((*kind, *count)),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Bytes from the start of a type to the end of its last member. Runtime arrays count
+    /// as empty, and matrix columns are assumed to be 16 byte aligned as in std140.
+    ///
+    /// `None` for types nested deeper than `MAX_TYPE_DEPTH`, which includes types that
+    /// contain themselves, and for sizes that don't fit in a `u32`.
+    fn size(&self, id: u32) -> Option<u32> {
+        self.nested_size(id, 0)
+    }
+
+    fn nested_size(&self, id: u32, depth: u32) -> Option<u32> {
+        if depth > MAX_TYPE_DEPTH {
+            return None;
+        }
+
+        match self.types.get(&id)? {
+            Type::Scalar { width, .. } => Some(width / 8),
+            Type::Vector { component, count } => self.nested_size(*component, depth + 1)?.checked_mul(*count),
+            Type::Matrix { column, count } => {
+                round_up(self.nested_size(*column, depth + 1)?, 16)?.checked_mul(*count)
+            }
+            Type::Array { element, length } => {
+                let stride = match self.decorations.get(&(id, DECORATION_ARRAY_STRIDE)) {
+                    Some(&stride) => stride,
+                    None => self.nested_size(*element, depth + 1)?,
+                };
+                stride.checked_mul(*self.constants.get(length)?)
+            }
+            Type::RuntimeArray => Some(0),
+            Type::Struct { members } => {
+                let mut end = 0;
+                for (index, &member) in members.iter().enumerate() {
+                    let offset = *self.member_offsets.get(&(id, index as u32))?;
+                    end = end.max(offset.checked_add(self.nested_size(member, depth + 1)?)?);
+                }
+                Some(end)
+            }
+            Type::Pointer { .. } => None,
+        }
+    }
+
+    /// The array stride of a block ending in a runtime array, like `s_instances[]`.
+    fn runtime_array_stride(&self, id: u32) -> Option<u32> {
+        match self.types.get(&id)? {
+            Type::Struct { members } => {
+                let &last = members.last()?;
+                match self.types.get(&last)? {
+                    Type::RuntimeArray => self.decorations.get(&(last, DECORATION_ARRAY_STRIDE)).copied(),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+/// `None` if the rounded size doesn't fit in a `u32`.
+fn round_up(size: u32, alignment: u32) -> Option<u32> {
+    size.checked_add((alignment - size % alignment) % alignment)
+}
+
+/// The GLSL name of a scalar or vector type, for error messages.
+fn glsl_type((kind, count): (ScalarKind, u32)) -> String {
+    let (scalar, prefix) = match kind {
+        ScalarKind::Bool => ("bool", "b"),
+        ScalarKind::Sint => ("int", "i"),
+        ScalarKind::Uint => ("uint", "u"),
+        ScalarKind::Float => ("float", ""),
+    };
+    match count {
+        1 => scalar.to_string(),
+        count => format!("{}vec{}", prefix, count),
+    }
+}
+
+/// What a vertex attribute format reads as in the shader, for the unnormalized formats.
+fn vertex_format_components(format: wgpu::VertexFormat) -> Option<(ScalarKind, u32)> {
+    use wgpu::VertexFormat::*;
+
+    match format {
+        Float => Some((ScalarKind::Float, 1)),
+        Float2 => Some((ScalarKind::Float, 2)),
+        Float3 => Some((ScalarKind::Float, 3)),
+        Float4 => Some((ScalarKind::Float, 4)),
+        Uint => Some((ScalarKind::Uint, 1)),
+        Uint2 => Some((ScalarKind::Uint, 2)),
+        Uint3 => Some((ScalarKind::Uint, 3)),
+        Uint4 => Some((ScalarKind::Uint, 4)),
+        Int => Some((ScalarKind::Sint, 1)),
+        Int2 => Some((ScalarKind::Sint, 2)),
+        Int3 => Some((ScalarKind::Sint, 3)),
+        Int4 => Some((ScalarKind::Sint, 4)),
+        _ => None,
+    }
+}
+
+/// The Rust type `State` fills each binding of its bind group with, and its size.
+fn binding_type(binding: u32) -> Option<(&'static str, usize)> {
+    match binding {
+        0 => Some(("Uniforms", std::mem::size_of::<Uniforms>())),
+        // Per element, since the instance buffer is a runtime array
+        1 => Some(("InstanceRaw", std::mem::size_of::<InstanceRaw>())),
+        2 => Some(("LightUniforms", std::mem::size_of::<LightUniforms>())),
+        3 => Some(("the material color", std::mem::size_of::<[f32; 4]>())),
+        _ => None,
+    }
+}
+
+/// Compare a shader's vertex inputs with `Vertex::descriptor`, and its buffer blocks with
+/// the types `State` binds, logging an error for each mismatch.
+///
+/// Returns `false` if anything didn't match. Shaders that can't be read are logged and
+/// skipped. Only meant for shaders written against `State`'s bind group layout; `State`
+/// runs it on its own shaders and custom materials in debug builds.
+pub fn check_layouts(name: &str, spirv: &[u32]) -> bool {
+    let reflection = match Reflection::parse(spirv) {
+        Ok(reflection) => reflection,
+        Err(e) => {
+            log::warn!("Skipped checking the layouts of {}: {}", name, e);
+            return true;
+        }
+    };
+
+    let mut matches = true;
+    for variable in &reflection.variables {
+        let ty = match reflection.pointee(variable.pointer_type) {
+            Some(ty) => ty,
+            None => continue,
+        };
+        let decoration = |decoration| reflection.decorations.get(&(variable.id, decoration)).copied();
+
+        match variable.storage_class {
+            STORAGE_CLASS_INPUT if reflection.execution_model == Some(EXECUTION_MODEL_VERTEX) => {
+                // Built-in inputs like gl_InstanceIndex have no location
+                let location = match decoration(DECORATION_LOCATION) {
+                    Some(location) => location,
+                    None => continue,
+                };
+                let shader_type = match reflection.components(ty) {
+                    Some(shader_type) => shader_type,
+                    None => continue,
+                };
+
+                let descriptor = Vertex::descriptor();
+                match descriptor.attributes.iter().find(|attribute| attribute.shader_location == location) {
+                    None => {
+                        log::error!(
+                            "{}: vertex input at location {} is missing from Vertex::descriptor()",
+                            name,
+                            location,
+                        );
+                        matches = false;
+                    }
+                    Some(attribute) => match vertex_format_components(attribute.format) {
+                        Some(format_type) if format_type != shader_type => {
+                            log::error!(
+                                "{}: vertex input at location {} is a {} but Vertex::descriptor() gives it {:?}",
+                                name,
+                                location,
+                                glsl_type(shader_type),
+                                attribute.format,
+                            );
+                            matches = false;
+                        }
+                        _ => {}
+                    },
+                }
+            }
+            // wgpu 0.5 has no push constants, so they can never be set
+            STORAGE_CLASS_PUSH_CONSTANT => {
+                log::error!("{}: push constants aren't supported, use a uniform block", name);
+                matches = false;
+            }
+            STORAGE_CLASS_UNIFORM | STORAGE_CLASS_STORAGE_BUFFER => {
+                if decoration(DECORATION_DESCRIPTOR_SET) != Some(0) {
+                    continue;
+                }
+                let binding = match decoration(DECORATION_BINDING) {
+                    Some(binding) => binding,
+                    None => continue,
+                };
+                let (rust_type, rust_size) = match binding_type(binding) {
+                    Some(expected) => expected,
+                    None => {
+                        log::error!("{}: binding {} isn't in State's bind group layout", name, binding);
+                        matches = false;
+                        continue;
+                    }
+                };
+
+                // Instances are compared per element, uniform blocks by their padded size
+                let (shader_size, rust_size) = match reflection.runtime_array_stride(ty) {
+                    Some(stride) => (stride, rust_size as u32),
+                    None => match reflection.size(ty).and_then(|size| round_up(size, 16)) {
+                        // The Rust types are a few hundred bytes at most, far from overflowing
+                        Some(size) => (size, round_up(rust_size as u32, 16).unwrap()),
+                        None => continue,
+                    },
+                };
+                if shader_size != rust_size {
+                    log::error!(
+                        "{}: binding {} is {} bytes in the shader but {} is {}",
+                        name,
+                        binding,
+                        shader_size,
+                        rust_type,
+                        rust_size,
+                    );
+                    matches = false;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    matches
+}
+
+#[cfg(test)]
+mod tests {
+    use wgpu::ShaderModuleSource;
+
+    use super::*;
+    use crate::graphics::shaders::ShaderCompiler;
+
+    fn builtin(source: &str, name: &str, kind: shaderc::ShaderKind, defines: &[&str]) -> Vec<u32> {
+        match ShaderCompiler::new().builtin_shader(source, name, kind, defines).unwrap() {
+            ShaderModuleSource::SpirV(spirv) => spirv.to_vec(),
+            _ => panic!("{} didn't compile to SPIR-V", name),
+        }
+    }
+
+    fn basic_vert() -> Vec<u32> {
+        builtin(include_str!("basic.vert"), "basic.vert", shaderc::ShaderKind::Vertex, &[])
+    }
+
+    fn basic_frag(defines: &[&str]) -> Vec<u32> {
+        builtin(include_str!("basic.frag"), "basic.frag", shaderc::ShaderKind::Fragment, defines)
+    }
+
+    fn pick_vert() -> Vec<u32> {
+        builtin(include_str!("pick.vert"), "pick.vert", shaderc::ShaderKind::Vertex, &[])
+    }
+
+    fn pick_frag() -> Vec<u32> {
+        builtin(include_str!("pick.frag"), "pick.frag", shaderc::ShaderKind::Fragment, &[])
+    }
+
+    fn variables(reflection: &Reflection, storage_classes: &[u32]) -> Vec<(u32, u32)> {
+        reflection
+            .variables
+            .iter()
+            .filter(|variable| storage_classes.contains(&variable.storage_class))
+            .filter_map(|variable| Some((variable.id, reflection.pointee(variable.pointer_type)?)))
+            .collect()
+    }
+
+    /// Vertex input locations and their GLSL types, by location.
+    fn inputs(reflection: &Reflection) -> Vec<(u32, String)> {
+        let mut inputs: Vec<_> = variables(reflection, &[STORAGE_CLASS_INPUT])
+            .into_iter()
+            .filter_map(|(id, ty)| {
+                let location = *reflection.decorations.get(&(id, DECORATION_LOCATION))?;
+                Some((location, glsl_type(reflection.components(ty)?)))
+            })
+            .collect();
+        inputs.sort();
+        inputs
+    }
+
+    /// Set, binding and size of each buffer block, by set and binding. Blocks ending in a
+    /// runtime array give the array stride instead.
+    fn bindings(reflection: &Reflection) -> Vec<(u32, u32, u32)> {
+        let mut bindings: Vec<_> = variables(reflection, &[STORAGE_CLASS_UNIFORM, STORAGE_CLASS_STORAGE_BUFFER])
+            .into_iter()
+            .map(|(id, ty)| {
+                let set = reflection.decorations[&(id, DECORATION_DESCRIPTOR_SET)];
+                let binding = reflection.decorations[&(id, DECORATION_BINDING)];
+                let size = reflection.runtime_array_stride(ty).or_else(|| reflection.size(ty)).unwrap();
+                (set, binding, size)
+            })
+            .collect();
+        bindings.sort();
+        bindings
+    }
+
+    fn has_push_constants(reflection: &Reflection) -> bool {
+        !variables(reflection, &[STORAGE_CLASS_PUSH_CONSTANT]).is_empty()
+    }
+
+    /// A module made of the given instructions, each an opcode followed by its operands.
+    fn module(instructions: &[&[u32]]) -> Vec<u32> {
+        let mut spirv = vec![MAGIC, 0x0001_0000, 0, 100, 0];
+        for instruction in instructions {
+            spirv.push((instruction.len() as u32) << 16 | instruction[0]);
+            spirv.extend_from_slice(&instruction[1..]);
+        }
+        spirv
+    }
+
+    #[test]
+    fn basic_vert_layout() {
+        let reflection = Reflection::parse(&basic_vert()).unwrap();
+
+        assert_eq!(reflection.execution_model, Some(EXECUTION_MODEL_VERTEX));
+        assert_eq!(
+            inputs(&reflection),
+            [(0, "vec3"), (1, "vec3"), (2, "vec3"), (3, "vec2")]
+                .iter()
+                .map(|&(location, ty)| (location, ty.to_string()))
+                .collect::<Vec<_>>(),
+        );
+        // Uniforms is a mat4, and each instance a mat4, a vec4 and a uint padded to 16 bytes
+        assert_eq!(bindings(&reflection), [(0, 0, 64), (0, 1, 96)]);
+        assert!(!has_push_constants(&reflection));
+    }
+
+    #[test]
+    fn basic_frag_layout() {
+        // 8 lights of 48 bytes, then the light count
+        let reflection = Reflection::parse(&basic_frag(&[])).unwrap();
+        assert_eq!(bindings(&reflection), [(0, 2, 388)]);
+        assert!(!has_push_constants(&reflection));
+
+        let reflection = Reflection::parse(&basic_frag(&["MATERIAL_COLOR"])).unwrap();
+        assert_eq!(bindings(&reflection), [(0, 2, 388), (0, 3, 16)]);
+        assert!(!has_push_constants(&reflection));
+    }
+
+    #[test]
+    fn pick_layout() {
+        let reflection = Reflection::parse(&pick_vert()).unwrap();
+        assert_eq!(bindings(&reflection), [(0, 0, 64), (0, 1, 96)]);
+        assert!(!has_push_constants(&reflection));
+
+        let reflection = Reflection::parse(&pick_frag()).unwrap();
+        assert_eq!(bindings(&reflection), []);
+        assert!(!has_push_constants(&reflection));
+    }
+
+    #[test]
+    fn builtin_shaders_match_rust_types() {
+        assert!(check_layouts("basic.vert", &basic_vert()));
+        for defines in &[&[][..], &["SRGB_INPUT"], &["MATERIAL_COLOR"], &["MATERIAL_COLOR", "SRGB_INPUT"]] {
+            assert!(check_layouts("basic.frag", &basic_frag(defines)));
+        }
+        assert!(check_layouts("pick.vert", &pick_vert()));
+        assert!(check_layouts("pick.frag", &pick_frag()));
+    }
+
+    #[test]
+    fn garbage_is_an_error() {
+        assert!(Reflection::parse(&[]).is_err());
+        assert!(Reflection::parse(&[1, 2, 3, 4, 5, 6]).is_err());
+
+        // An OpTypeVector claiming four words with only two left
+        let header = [MAGIC, 0x0001_0000, 0, 1, 0];
+        assert!(Reflection::parse(&[&header[..], &[4 << 16 | OP_TYPE_VECTOR, 1]].concat()).is_err());
+        // A zero word count would never advance
+        assert!(Reflection::parse(&[&header[..], &[OP_TYPE_VECTOR]].concat()).is_err());
+    }
+
+    #[test]
+    fn truncated_shaders_do_not_panic() {
+        let spirv = basic_vert();
+        for len in 0..spirv.len() {
+            let _ = Reflection::parse(&spirv[..len]);
+            check_layouts("truncated basic.vert", &spirv[..len]);
+        }
+
+        // Cut the first instruction after the header one word short
+        let count = (spirv[5] >> 16) as usize;
+        assert!(Reflection::parse(&spirv[..5 + count - 1]).is_err());
+    }
+
+    #[test]
+    fn self_referential_types_do_not_recurse_forever() {
+        let reflection = Reflection::parse(&module(&[
+            &[OP_TYPE_VECTOR, 1, 1, 4],
+            &[OP_TYPE_INT, 2, 32, 0],
+            &[OP_CONSTANT, 2, 3, 4],
+            &[OP_TYPE_ARRAY, 4, 4, 3],
+            &[OP_TYPE_STRUCT, 5, 6],
+            &[OP_TYPE_STRUCT, 6, 5],
+            &[OP_MEMBER_DECORATE, 5, 0, DECORATION_OFFSET, 0],
+            &[OP_MEMBER_DECORATE, 6, 0, DECORATION_OFFSET, 0],
+        ]))
+        .unwrap();
+
+        assert_eq!(reflection.components(1), None);
+        assert_eq!(reflection.size(1), None);
+        assert_eq!(reflection.size(4), None);
+        assert_eq!(reflection.size(5), None);
+    }
+
+    #[test]
+    fn overflowing_sizes_are_none() {
+        let float = [OP_TYPE_FLOAT, 1, 32];
+        let uint = [OP_TYPE_INT, 2, 32, 0];
+        let spirv = module(&[
+            &float,
+            &uint,
+            &[OP_CONSTANT, 2, 3, u32::MAX],
+            // stride * length
+            &[OP_TYPE_ARRAY, 4, 1, 3],
+            &[OP_DECORATE, 4, DECORATION_ARRAY_STRIDE, 16],
+            // offset + size
+            &[OP_TYPE_STRUCT, 5, 1],
+            &[OP_MEMBER_DECORATE, 5, 0, DECORATION_OFFSET, u32::MAX - 1],
+            // Fits, but not once rounded up to 16 bytes
+            &[OP_TYPE_STRUCT, 6, 1],
+            &[OP_MEMBER_DECORATE, 6, 0, DECORATION_OFFSET, u32::MAX - 4],
+            &[OP_TYPE_POINTER, 7, STORAGE_CLASS_UNIFORM, 6],
+            &[OP_VARIABLE, 7, 8, STORAGE_CLASS_UNIFORM],
+            &[OP_DECORATE, 8, DECORATION_DESCRIPTOR_SET, 0],
+            &[OP_DECORATE, 8, DECORATION_BINDING, 0],
+        ]);
+        let reflection = Reflection::parse(&spirv).unwrap();
+
+        assert_eq!(reflection.size(4), None);
+        assert_eq!(reflection.size(5), None);
+        assert_eq!(reflection.size(6), Some(u32::MAX));
+        // The block is skipped rather than compared, and nothing panics
+        assert!(check_layouts("overflowing", &spirv));
+    }
+}
@@ -1,4 +1,4 @@
-use crate::graphics::{Color, Vertex, Object};
+use crate::graphics::{Color, Vertex, Object, PipelineConfig};
 
 #[derive(Clone, Debug)]
 pub struct Shape {
@@ -8,7 +8,7 @@ pub struct Shape {
 
 impl Shape {
     pub fn to_object(&self, device: &wgpu::Device) -> Object {
-        Object::new(device, &self.vertices, &self.indices)
+        Object::new(device, &self.vertices, Some(&self.indices), PipelineConfig::default())
     }
 }
 
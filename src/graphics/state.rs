@@ -1,86 +1,433 @@
-use winit::{event::WindowEvent, window::Window};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::time::{Duration, Instant};
+use anyhow::Context;
+use winit::{
+    event::{Event, WindowEvent},
+    event_loop::ControlFlow,
+    window::Window,
+};
 
+use crate::common::math::{bounds::Aabb, euler_rotation, frustum::Frustum, intersection, Vector2f};
 use crate::graphics::{
-    shaders, Camera, CameraController, GraphicsConfig, Uniforms, Vertex, Object,
+    config, light, shaders, BenchResult, Camera, CameraController, Color, FrameStats, GraphicsConfig, Instance, InstanceData, InstanceRaw,
+    Layer, Light, LightUniforms, MaterialId, ObjectData, OverlayCamera, PipelineConfig, RenderCallback, RenderContext, SceneData, ShaderCompiler, Uniforms, Vertex,
+    Object,
 };
 
+/// Owns the GPU resources and everything drawn with them.
+///
+/// Sizes and pixel coordinates are always in physical pixels, as reported by winit.
 pub struct State {
     config: GraphicsConfig,
     camera: Camera,
     camera_controller: CameraController,
     uniforms: Uniforms,
-    gpu: GpuState,
+    overlay_camera: OverlayCamera,
+    overlay_uniforms: Uniforms,
+    lights: Vec<Light>,
     size: winit::dpi::PhysicalSize<u32>,
-    objects: Vec<Object>,
+    // A minimized window reports a zero size, which can't back a swap chain
+    is_minimized: bool,
+    // Skips `update` and `render` until resumed
+    paused: bool,
+    // Keyed by a monotonic id so removing an object never invalidates the others,
+    // and ordered so objects still draw in creation order.
+    objects: BTreeMap<usize, Object>,
+    next_object_id: usize,
+    last_render: Option<Instant>,
+    last_frame_stats: FrameStats,
+    render_callback: Option<RenderCallback>,
+    frame_times: VecDeque<Duration>,
+    // Line list vertices queued by `draw_line` for the next frame
+    debug_lines: Vec<Vertex>,
+    // Created the first time debug lines are drawn, then reused
+    debug_line_object: Option<Object>,
+    // Last, so objects and the render callback release their GPU resources before the
+    // device goes away
+    gpu: GpuState,
 }
 
+/// Number of frames averaged by [`State::fps`].
+const FPS_SAMPLE_COUNT: usize = 60;
+
+/// Render target format for GPU picking: object id plus one, and instance index.
+const PICK_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rg32Uint;
+
+/// Texture to buffer copies must use rows padded to a multiple of this many bytes.
+const COPY_BYTES_PER_ROW_ALIGNMENT: u32 = 256;
+
+// Fields drop in declaration order: resources created from the device first, then the
+// swap chain before the surface it presents to, and the queue and device last. Headless
+// states have neither a surface nor a swap chain.
 struct GpuState {
-    surface: wgpu::Surface,
-    device: wgpu::Device,
-    queue: wgpu::Queue,
-    sc_desc: wgpu::SwapChainDescriptor,
-    swap_chain: wgpu::SwapChain,
-    render_pipeline: wgpu::RenderPipeline,
+    compiler: ShaderCompiler,
+    materials: Vec<Material>,
+    vs_module: wgpu::ShaderModule,
+    fs_module: wgpu::ShaderModule,
+    material_fs_module: wgpu::ShaderModule,
+    pick_vs_module: wgpu::ShaderModule,
+    pick_fs_module: wgpu::ShaderModule,
+    render_pipeline_layout: wgpu::PipelineLayout,
+    pipelines: HashMap<PipelineConfig, wgpu::RenderPipeline>,
+    pick_pipelines: HashMap<PipelineConfig, wgpu::RenderPipeline>,
 
     uniform_buffer: wgpu::Buffer,
+    overlay_uniform_buffer: wgpu::Buffer,
+    light_buffer: wgpu::Buffer,
+    // Never has any lights, so screen space objects stay unlit
+    overlay_light_buffer: wgpu::Buffer,
+    uniform_bind_group_layout: wgpu::BindGroupLayout,
+    // Bound at the start of each pass, with a placeholder instance buffer
     uniform_bind_group: wgpu::BindGroup,
+    // Color passes draw into this and resolve into their target when multisampling
+    multisampled_framebuffer: Option<(wgpu::Texture, wgpu::TextureView)>,
+    sample_count: u32,
+
+    sc_desc: wgpu::SwapChainDescriptor,
+    adapter_info: wgpu::AdapterInfo,
+    anisotropy_clamp: Option<u8>,
+    swap_chain: Option<wgpu::SwapChain>,
+    surface: Option<wgpu::Surface>,
+    queue: wgpu::Queue,
+    device: wgpu::Device,
+}
+
+/// Custom shaders created by `State::create_material`, indexed by `MaterialId`.
+struct Material {
+    vs_module: wgpu::ShaderModule,
+    fs_module: wgpu::ShaderModule,
+}
+
+impl GpuState {
+    /// Create the color and picking pipelines for `config` unless they're already cached.
+    fn ensure_pipeline(&mut self, config: PipelineConfig) {
+        if self.pipelines.contains_key(&config) {
+            return;
+        }
+
+        let (vs_module, fs_module) = match config.material {
+            Some(MaterialId(index)) => (&self.materials[index].vs_module, &self.materials[index].fs_module),
+            None if config.material_color => (&self.vs_module, &self.material_fs_module),
+            None => (&self.vs_module, &self.fs_module),
+        };
+        let render_pipeline = self.create_pipeline(
+            config,
+            vs_module,
+            fs_module,
+            self.sc_desc.format,
+            self.sample_count,
+            config.alpha_to_coverage,
+        );
+        // Ids can't be blended, resolved or covered partially, so picking is never
+        // multisampled
+        let pick_pipeline = self.create_pipeline(
+            config,
+            &self.pick_vs_module,
+            &self.pick_fs_module,
+            PICK_FORMAT,
+            1,
+            false,
+        );
+
+        self.pipelines.insert(config, render_pipeline);
+        self.pick_pipelines.insert(config, pick_pipeline);
+    }
+
+    fn create_pipeline(
+        &self,
+        config: PipelineConfig,
+        vs_module: &wgpu::ShaderModule,
+        fs_module: &wgpu::ShaderModule,
+        format: wgpu::TextureFormat,
+        sample_count: u32,
+        alpha_to_coverage: bool,
+    ) -> wgpu::RenderPipeline {
+        self.device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            layout: &self.render_pipeline_layout,
+            vertex_stage: wgpu::ProgrammableStageDescriptor {
+                module: vs_module,
+                entry_point: "main",
+            },
+            fragment_stage: Some(wgpu::ProgrammableStageDescriptor {
+                module: fs_module,
+                entry_point: "main",
+            }),
+            rasterization_state: Some(wgpu::RasterizationStateDescriptor {
+                front_face: config.front_face,
+                cull_mode: config.cull_mode,
+                depth_bias: config.depth_bias.constant,
+                depth_bias_slope_scale: config.depth_bias.slope_scale,
+                depth_bias_clamp: config.depth_bias.clamp,
+            }),
+            primitive_topology: config.topology,
+            color_states: &[wgpu::ColorStateDescriptor {
+                format,
+                color_blend: wgpu::BlendDescriptor::REPLACE,
+                alpha_blend: wgpu::BlendDescriptor::REPLACE,
+                write_mask: wgpu::ColorWrite::ALL,
+            }],
+            depth_stencil_state: None,
+            vertex_state: wgpu::VertexStateDescriptor {
+                index_format: wgpu::IndexFormat::Uint16,
+                vertex_buffers: &[Vertex::descriptor()],
+            },
+            sample_count,
+            sample_mask: config.sample_mask,
+            alpha_to_coverage_enabled: alpha_to_coverage,
+        })
+    }
+
+    fn recreate_swap_chain(&mut self) {
+        if let Some(surface) = &self.surface {
+            self.swap_chain = Some(self.device.create_swap_chain(surface, &self.sc_desc));
+        }
+    }
+
+    fn recreate_multisampled_framebuffer(&mut self) {
+        self.multisampled_framebuffer = create_multisampled_framebuffer(&self.device, &self.sc_desc, self.sample_count);
+    }
+
+    /// The view color passes draw into before resolving, if multisampling is on.
+    fn multisampled_view(&self) -> Option<&wgpu::TextureView> {
+        self.multisampled_framebuffer.as_ref().map(|(_, view)| view)
+    }
+
+    /// Create a bind group pairing one of the shared sets of uniform buffers with an
+    /// object's instance and material buffers.
+    fn create_bind_group(&self, object: &Object) -> wgpu::BindGroup {
+        let (uniform_buffer, light_buffer) = if object.is_screen_space() {
+            (&self.overlay_uniform_buffer, &self.overlay_light_buffer)
+        } else {
+            (&self.uniform_buffer, &self.light_buffer)
+        };
+
+        self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &self.uniform_bind_group_layout,
+            bindings: &[
+                wgpu::Binding {
+                    binding: 0,
+                    resource: wgpu::BindingResource::Buffer {
+                        buffer: uniform_buffer,
+                        range: 0..std::mem::size_of::<Uniforms>() as wgpu::BufferAddress,
+                    },
+                },
+                wgpu::Binding {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Buffer {
+                        buffer: object.instance_buffer(),
+                        range: 0..object.instance_buffer_size() as wgpu::BufferAddress,
+                    },
+                },
+                wgpu::Binding {
+                    binding: 2,
+                    resource: wgpu::BindingResource::Buffer {
+                        buffer: light_buffer,
+                        range: 0..std::mem::size_of::<LightUniforms>() as wgpu::BufferAddress,
+                    },
+                },
+                wgpu::Binding {
+                    binding: 3,
+                    resource: wgpu::BindingResource::Buffer {
+                        buffer: object.material_buffer(),
+                        range: 0..std::mem::size_of::<[f32; 4]>() as wgpu::BufferAddress,
+                    },
+                },
+            ],
+            label: Some("object_bind_group"),
+        })
+    }
+}
+
+/// Pick the first of `preferences` the backend supports, falling back to `Fifo`.
+///
+/// wgpu can't report which modes a surface supports, so support is judged by what each
+/// backend's swap chain can offer. If a driver turns out not to support the chosen mode
+/// wgpu falls back to `Fifo`, which is always available, instead of failing.
+fn select_present_mode(backend: wgpu::Backend, preferences: &[wgpu::PresentMode]) -> wgpu::PresentMode {
+    use wgpu::{Backend, PresentMode};
+
+    let supported: &[PresentMode] = match backend {
+        Backend::Vulkan | Backend::Dx12 => &[PresentMode::Fifo, PresentMode::Mailbox, PresentMode::Immediate],
+        Backend::Metal | Backend::Dx11 => &[PresentMode::Fifo, PresentMode::Immediate],
+        _ => &[PresentMode::Fifo],
+    };
+
+    preferences
+        .iter()
+        .copied()
+        .find(|mode| supported.contains(mode))
+        .unwrap_or(PresentMode::Fifo)
+}
+
+/// Sample counts every primary backend supports; fall back to no multisampling for anything else.
+fn validate_sample_count(sample_count: u32) -> u32 {
+    match sample_count {
+        1 | 2 | 4 | 8 => sample_count,
+        _ => {
+            log::warn!("{} samples per pixel isn't supported, disabling multisampling", sample_count);
+            1
+        }
+    }
+}
+
+/// A texture the size and format of the swap chain with `sample_count` samples per pixel,
+/// or `None` without multisampling.
+fn create_multisampled_framebuffer(
+    device: &wgpu::Device,
+    sc_desc: &wgpu::SwapChainDescriptor,
+    sample_count: u32,
+) -> Option<(wgpu::Texture, wgpu::TextureView)> {
+    if sample_count == 1 {
+        return None;
+    }
+
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("multisampled_framebuffer"),
+        size: wgpu::Extent3d {
+            width: sc_desc.width,
+            height: sc_desc.height,
+            depth: 1,
+        },
+        array_layer_count: 1,
+        mip_level_count: 1,
+        sample_count,
+        dimension: wgpu::TextureDimension::D2,
+        format: sc_desc.format,
+        usage: wgpu::TextureUsage::OUTPUT_ATTACHMENT,
+    });
+    let view = texture.create_default_view();
+    Some((texture, view))
+}
+
+/// Swap chains only accept 8 bit BGRA/RGBA formats; fall back to the default for anything else.
+fn validate_surface_format(format: wgpu::TextureFormat) -> wgpu::TextureFormat {
+    use wgpu::TextureFormat;
+
+    match format {
+        TextureFormat::Bgra8UnormSrgb
+        | TextureFormat::Bgra8Unorm
+        | TextureFormat::Rgba8UnormSrgb
+        | TextureFormat::Rgba8Unorm => format,
+        _ => {
+            log::warn!(
+                "{:?} can't be used as a surface format, falling back to {:?}",
+                format,
+                config::DEFAULT_SURFACE_FORMAT
+            );
+            config::DEFAULT_SURFACE_FORMAT
+        }
+    }
 }
 
 impl State {
     pub async fn new(window: &Window, config: GraphicsConfig) -> anyhow::Result<Self> {
-        let size = window.inner_size();
-
         let surface = wgpu::Surface::create(window);
+        Self::with_surface(Some(surface), window.inner_size(), config).await
+    }
+
+    /// A `State` without a window, rendering only into offscreen textures with `bench` and
+    /// `render_to_rgba`. `size` stands in for the window size and must not be zero.
+    ///
+    /// `render` fails since there's nothing to present to, and `handle_event` needs a window.
+    pub async fn new_headless(size: winit::dpi::PhysicalSize<u32>, config: GraphicsConfig) -> anyhow::Result<Self> {
+        anyhow::ensure!(
+            size.width > 0 && size.height > 0,
+            "headless size {}x{} must not be zero",
+            size.width,
+            size.height
+        );
+        Self::with_surface(None, size, config).await
+    }
+
+    pub fn is_headless(&self) -> bool {
+        self.gpu.surface.is_none()
+    }
 
+    async fn with_surface(
+        surface: Option<wgpu::Surface>,
+        size: winit::dpi::PhysicalSize<u32>,
+        config: GraphicsConfig,
+    ) -> anyhow::Result<Self> {
         let adapter = wgpu::Adapter::request(
             &wgpu::RequestAdapterOptions {
                 power_preference: wgpu::PowerPreference::Default,
-                compatible_surface: Some(&surface),
+                compatible_surface: surface.as_ref(),
             },
             wgpu::BackendBit::PRIMARY, // Vulkan + Metal + DX12 + Browser WebGPU
         )
         .await
-        .unwrap();
+        .context("No GPU adapter is available")?;
+
+        let anisotropy_clamp = match config.max_anisotropy {
+            Some(_) if !adapter.extensions().anisotropic_filtering => {
+                log::warn!("Anisotropic filtering is not supported by this adapter, disabling it");
+                None
+            }
+            max_anisotropy => max_anisotropy,
+        };
 
         let (device, queue) = adapter
             .request_device(&wgpu::DeviceDescriptor {
                 extensions: wgpu::Extensions {
-                    anisotropic_filtering: false,
+                    anisotropic_filtering: anisotropy_clamp.is_some(),
                 },
                 limits: Default::default(),
             })
             .await;
 
+        let adapter_info = adapter.get_info();
+
         let sc_desc = wgpu::SwapChainDescriptor {
             usage: wgpu::TextureUsage::OUTPUT_ATTACHMENT,
-            format: wgpu::TextureFormat::Bgra8UnormSrgb,
+            format: validate_surface_format(config.surface_format),
             width: size.width,
             height: size.height,
-            present_mode: wgpu::PresentMode::Fifo,
-        };
-        let swap_chain = device.create_swap_chain(&surface, &sc_desc);
-
-        let camera = Camera {
-            eye: (0.0, 1.0, 50.0).into(),
-            up: cgmath::Vector3::unit_y(),
-            aspect: sc_desc.width as f32 / sc_desc.height as f32,
-            fovy: 45.0,
-            znear: 0.1,
-            zfar: 100.0,
+            present_mode: select_present_mode(adapter_info.backend, &config.present_modes),
         };
+        let swap_chain = surface.as_ref().map(|surface| device.create_swap_chain(surface, &sc_desc));
+        let sample_count = validate_sample_count(config.sample_count);
+        let multisampled_framebuffer = create_multisampled_framebuffer(&device, &sc_desc, sample_count);
+
+        let camera = Camera::new(config.initial_eye, sc_desc.width as f32 / sc_desc.height as f32)
+            .looking_at(config.initial_target);
 
         let camera_controller = CameraController::new(0.2);
 
         let mut uniforms = Uniforms::new();
         uniforms.update_view_proj(&camera);
 
-        let instance_buffer = device.create_buffer_with_data(&[0, 1, 2],  wgpu::BufferUsage::STORAGE_READ);
+        // The scene bind group needs an instance buffer even though it never draws from it: objects
+        // aren't drawn until they have instances, and then they bind their own. One zeroed
+        // instance keeps the binding a valid, whole InstanceRaw.
+        let placeholder_instance: InstanceRaw = bytemuck::Zeroable::zeroed();
+        let placeholder_instance_buffer = device.create_buffer_with_data(
+            bytemuck::cast_slice(&[placeholder_instance]),
+            wgpu::BufferUsage::STORAGE_READ,
+        );
 
         let uniform_buffer = device.create_buffer_with_data(
             bytemuck::cast_slice(&[uniforms]),
             wgpu::BufferUsage::UNIFORM | wgpu::BufferUsage::COPY_DST,
         );
 
+        let overlay_camera = OverlayCamera::default();
+        let mut overlay_uniforms = Uniforms::new();
+        overlay_uniforms.set_view_proj(overlay_camera.build_projection_matrix(size.width as f32, size.height as f32));
+        let overlay_uniform_buffer = device.create_buffer_with_data(
+            bytemuck::cast_slice(&[overlay_uniforms]),
+            wgpu::BufferUsage::UNIFORM | wgpu::BufferUsage::COPY_DST,
+        );
+
+        let light_uniforms = LightUniforms::new(&[]);
+        let light_buffer = device.create_buffer_with_data(
+            bytemuck::cast_slice(&[light_uniforms]),
+            wgpu::BufferUsage::UNIFORM | wgpu::BufferUsage::COPY_DST,
+        );
+        let overlay_light_buffer = device.create_buffer_with_data(
+            bytemuck::cast_slice(&[light_uniforms]),
+            wgpu::BufferUsage::UNIFORM,
+        );
+
         let uniform_bind_group_layout =
             device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
                 bindings: &[
@@ -99,6 +446,16 @@ impl State {
                             readonly: true,
                         },
                     },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 2,
+                        visibility: wgpu::ShaderStage::FRAGMENT,
+                        ty: wgpu::BindingType::UniformBuffer { dynamic: false },
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 3,
+                        visibility: wgpu::ShaderStage::FRAGMENT,
+                        ty: wgpu::BindingType::UniformBuffer { dynamic: false },
+                    },
                 ],
                 label: Some("uniform_bind_group_layout"),
             });
@@ -116,267 +473,1337 @@ impl State {
                 wgpu::Binding {
                     binding: 1,
                     resource: wgpu::BindingResource::Buffer {
-                        buffer: &instance_buffer,
-                        range: 0..1 as wgpu::BufferAddress,
+                        buffer: &placeholder_instance_buffer,
+                        range: 0..std::mem::size_of::<InstanceRaw>() as wgpu::BufferAddress,
+                    },
+                },
+                wgpu::Binding {
+                    binding: 2,
+                    resource: wgpu::BindingResource::Buffer {
+                        buffer: &light_buffer,
+                        range: 0..std::mem::size_of_val(&light_uniforms) as wgpu::BufferAddress,
+                    },
+                },
+                wgpu::Binding {
+                    binding: 3,
+                    resource: wgpu::BindingResource::Buffer {
+                        buffer: &uniform_buffer,
+                        range: 0..std::mem::size_of::<[f32; 4]>() as wgpu::BufferAddress,
                     },
                 },
             ],
             label: Some("uniform_bind_group"),
         });
 
-        let mut compiler = shaders::ShaderCompiler::new()?;
+        let mut compiler = ShaderCompiler::new();
         let vs_module = shaders::basic::vertex_module(&device, &mut compiler)?;
-        let fs_module = shaders::basic::fragment_module(&device, &mut compiler)?;
+        let fs_module = shaders::basic::fragment_module(&device, &mut compiler, config.srgb_colors)?;
+        let material_fs_module =
+            shaders::basic::material_fragment_module(&device, &mut compiler, config.srgb_colors)?;
+        let pick_vs_module = shaders::pick::vertex_module(&device, &mut compiler)?;
+        let pick_fs_module = shaders::pick::fragment_module(&device, &mut compiler)?;
 
         let render_pipeline_layout =
             device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
                 bind_group_layouts: &[&uniform_bind_group_layout],
             });
 
-        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            layout: &render_pipeline_layout,
-            vertex_stage: wgpu::ProgrammableStageDescriptor {
-                module: &vs_module,
-                entry_point: "main",
-            },
-            fragment_stage: Some(wgpu::ProgrammableStageDescriptor {
-                module: &fs_module,
-                entry_point: "main",
-            }),
-            rasterization_state: Some(wgpu::RasterizationStateDescriptor {
-                front_face: wgpu::FrontFace::Ccw,
-                cull_mode: wgpu::CullMode::Back,
-                depth_bias: 0,
-                depth_bias_slope_scale: 0.0,
-                depth_bias_clamp: 0.0,
-            }),
-            primitive_topology: wgpu::PrimitiveTopology::TriangleList,
-            color_states: &[wgpu::ColorStateDescriptor {
-                format: sc_desc.format,
-                color_blend: wgpu::BlendDescriptor::REPLACE,
-                alpha_blend: wgpu::BlendDescriptor::REPLACE,
-                write_mask: wgpu::ColorWrite::ALL,
-            }],
-            depth_stencil_state: None,
-            vertex_state: wgpu::VertexStateDescriptor {
-                index_format: wgpu::IndexFormat::Uint16,
-                vertex_buffers: &[Vertex::descriptor()],
-            },
-            sample_count: 1,
-            sample_mask: !0,
-            alpha_to_coverage_enabled: false,
-        });
+        let mut gpu = GpuState {
+            compiler,
+            materials: Vec::new(),
+            surface,
+            device,
+            queue,
+            sc_desc,
+            swap_chain,
+            adapter_info,
+            anisotropy_clamp,
+            vs_module,
+            fs_module,
+            material_fs_module,
+            pick_vs_module,
+            pick_fs_module,
+            render_pipeline_layout,
+            pipelines: HashMap::new(),
+            pick_pipelines: HashMap::new(),
+            uniform_buffer,
+            overlay_uniform_buffer,
+            light_buffer,
+            overlay_light_buffer,
+            uniform_bind_group_layout,
+            uniform_bind_group,
+            multisampled_framebuffer,
+            sample_count,
+        };
+        gpu.ensure_pipeline(PipelineConfig::default());
 
         Ok(Self {
             config,
             camera,
             camera_controller,
             uniforms,
+            overlay_camera,
+            overlay_uniforms,
+            lights: Vec::new(),
             size,
-            objects: Vec::new(),
-            gpu: GpuState {
-                surface,
-                device,
-                queue,
-                sc_desc,
-                swap_chain,
-                render_pipeline,
-                uniform_buffer,
-                uniform_bind_group,
-            },
+            is_minimized: size.width == 0 || size.height == 0,
+            paused: false,
+            objects: BTreeMap::new(),
+            next_object_id: 0,
+            last_render: None,
+            last_frame_stats: FrameStats::default(),
+            render_callback: None,
+            frame_times: VecDeque::with_capacity(FPS_SAMPLE_COUNT),
+            debug_lines: Vec::new(),
+            debug_line_object: None,
+            gpu,
         })
     }
 
     pub fn create_object(&mut self, vertices: &[Vertex], indices: &[u16]) -> usize {
-        let object = Object::new(&self.gpu.device, vertices, indices);
-        self.objects.push(object);
-        self.objects.len() - 1
+        self.create_object_with_config(vertices, indices, PipelineConfig::default())
     }
 
-    pub fn create_instance(&mut self, object_id: usize, position: cgmath::Vector3<f32>, rotation: cgmath::Quaternion<f32>) -> Option<usize> {
-        match self.objects.get_mut(object_id) {
-            Some(object) => {
-                object.add_instance(&self.gpu.device, position, rotation);
-
-                let uniform_bind_group_layout =
-                self.gpu.device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-                    bindings: &[
-                        wgpu::BindGroupLayoutEntry {
-                            binding: 0,
-                            visibility: wgpu::ShaderStage::VERTEX,
-                            ty: wgpu::BindingType::UniformBuffer { dynamic: false },
-                        },
-                        wgpu::BindGroupLayoutEntry {
-                            binding: 1,
-                            visibility: wgpu::ShaderStage::VERTEX,
-                            ty: wgpu::BindingType::StorageBuffer {
-                                // We don't plan on changing the size of this buffer
-                                dynamic: false,
-                                // The shader is not allowed to modify it's contents
-                                readonly: true,
-                            },
-                        },
-                    ],
-                    label: Some("uniform_bind_group_layout"),
-                });
-        
-                let uniform_bind_group = self.gpu.device.create_bind_group(&wgpu::BindGroupDescriptor {
-                    layout: &uniform_bind_group_layout,
-                    bindings: &[
-                        wgpu::Binding {
-                            binding: 0,
-                            resource: wgpu::BindingResource::Buffer {
-                                buffer: &self.gpu.uniform_buffer,
-                                // FYI: you can share a single buffer between bindings.
-                                range: 0..std::mem::size_of_val(&self.uniforms) as wgpu::BufferAddress,
-                            },
-                        },
-                        wgpu::Binding {
-                            binding: 1,
-                            resource: wgpu::BindingResource::Buffer {
-                                buffer: &object.instance_buffer(),
-                                range: 0..object.instance_buffer_size() as wgpu::BufferAddress,
-                            },
-                        },
-                    ],
-                    label: Some("uniform_bind_group"),
-                });
-        
-                self.gpu.uniform_bind_group = uniform_bind_group;
+    /// Create an object drawn with non-default pipeline settings, such as its cull mode or
+    /// topology.
+    pub fn create_object_with_config(&mut self, vertices: &[Vertex], indices: &[u16], pipeline_config: PipelineConfig) -> usize {
+        self.insert_object(vertices, Some(indices), pipeline_config)
+    }
 
-                let mut compiler = shaders::ShaderCompiler::new().unwrap();
-                let vs_module = shaders::basic::vertex_module(&self.gpu.device, &mut compiler).unwrap();
-                let fs_module = shaders::basic::fragment_module(&self.gpu.device, &mut compiler).unwrap();
+    /// Create an object without an index buffer, drawn straight from its vertices.
+    pub fn create_object_unindexed(&mut self, vertices: &[Vertex]) -> usize {
+        self.create_object_unindexed_with_config(vertices, PipelineConfig::default())
+    }
 
-                let render_pipeline_layout =
-                self.gpu.device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-                    bind_group_layouts: &[&uniform_bind_group_layout],
-                });
-    
-                self.gpu.render_pipeline = self.gpu.device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-                    layout: &render_pipeline_layout,
-                    vertex_stage: wgpu::ProgrammableStageDescriptor {
-                        module: &vs_module,
-                        entry_point: "main",
-                    },
-                    fragment_stage: Some(wgpu::ProgrammableStageDescriptor {
-                        module: &fs_module,
-                        entry_point: "main",
-                    }),
-                    rasterization_state: Some(wgpu::RasterizationStateDescriptor {
-                        front_face: wgpu::FrontFace::Ccw,
-                        cull_mode: wgpu::CullMode::Back,
-                        depth_bias: 0,
-                        depth_bias_slope_scale: 0.0,
-                        depth_bias_clamp: 0.0,
-                    }),
-                    primitive_topology: wgpu::PrimitiveTopology::TriangleList,
-                    color_states: &[wgpu::ColorStateDescriptor {
-                        format: self.gpu.sc_desc.format,
-                        color_blend: wgpu::BlendDescriptor::REPLACE,
-                        alpha_blend: wgpu::BlendDescriptor::REPLACE,
-                        write_mask: wgpu::ColorWrite::ALL,
-                    }],
-                    depth_stencil_state: None,
-                    vertex_state: wgpu::VertexStateDescriptor {
-                        index_format: wgpu::IndexFormat::Uint16,
-                        vertex_buffers: &[Vertex::descriptor()],
-                    },
-                    sample_count: 1,
-                    sample_mask: !0,
-                    alpha_to_coverage_enabled: false,
-                });
-        
-                Some(object.num_instances() - 1)
-            },
-            None => None
+    /// Create an unindexed object with non-default pipeline settings, such as a line list.
+    pub fn create_object_unindexed_with_config(&mut self, vertices: &[Vertex], pipeline_config: PipelineConfig) -> usize {
+        self.insert_object(vertices, None, pipeline_config)
+    }
+
+    /// Create an object drawn with a custom material's shaders.
+    pub fn create_object_with_material(&mut self, vertices: &[Vertex], indices: &[u16], material: MaterialId) -> usize {
+        let pipeline_config = PipelineConfig {
+            material: Some(material),
+            ..PipelineConfig::default()
+        };
+        self.create_object_with_config(vertices, indices, pipeline_config)
+    }
+
+    fn insert_object(&mut self, vertices: &[Vertex], indices: Option<&[u16]>, mut pipeline_config: PipelineConfig) -> usize {
+        if !self.material_exists(pipeline_config.material) {
+            log::warn!("{:?} doesn't exist, using the built-in shaders", pipeline_config.material);
+            pipeline_config.material = None;
         }
+        self.gpu.ensure_pipeline(pipeline_config);
+
+        let mut object = Object::new(&self.gpu.device, vertices, indices, pipeline_config);
+        object.set_max_instances(self.config.max_instances);
+        let object_id = self.next_object_id;
+        self.next_object_id += 1;
+        self.objects.insert(object_id, object);
+        object_id
     }
 
-    pub fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>) {
-        self.size = new_size;
-        self.gpu.sc_desc.width = new_size.width;
-        self.gpu.sc_desc.height = new_size.height;
-        self.gpu.swap_chain = self
-            .gpu
-            .device
-            .create_swap_chain(&self.gpu.surface, &self.gpu.sc_desc);
+    /// Load an OBJ file as a new object, returning its id.
+    #[cfg(feature = "tobj")]
+    pub fn load_obj(&mut self, path: &std::path::Path) -> anyhow::Result<usize> {
+        let (vertices, indices) = crate::graphics::obj::load_obj(path)?;
+        Ok(self.create_object(&vertices, &indices))
     }
 
-    pub fn input(&mut self, event: &WindowEvent) -> bool {
-        self.camera_controller.process_events(event)
+    /// Remove an object and free its GPU buffers. Ids of other objects stay valid.
+    pub fn remove_object(&mut self, object_id: usize) -> bool {
+        self.objects.remove(&object_id).is_some()
     }
 
-    pub fn update(&mut self) {
-        self.camera_controller.update_camera(&mut self.camera);
-        self.uniforms.update_view_proj(&self.camera);
+    /// Replace an object's vertices and indices, keeping its id and instances. Pass `None`
+    /// for `indices` to draw the new vertices unindexed.
+    pub fn update_object_geometry(&mut self, object_id: usize, vertices: &[Vertex], indices: Option<&[u16]>) -> bool {
+        match self.objects.get_mut(&object_id) {
+            Some(object) => {
+                object.set_geometry(&self.gpu.device, vertices, indices);
+                true
+            }
+            None => false,
+        }
+    }
 
-        // Copy operation's are performed on the gpu, so we'll need
-        // a CommandEncoder for that
-        let mut encoder = self
-            .gpu
-            .device
-            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
-                label: Some("update encoder"),
-            });
+    pub fn object_exists(&self, object_id: usize) -> bool {
+        self.objects.contains_key(&object_id)
+    }
 
-        let staging_buffer = self.gpu.device.create_buffer_with_data(
-            bytemuck::cast_slice(&[self.uniforms]),
-            wgpu::BufferUsage::COPY_SRC,
-        );
+    /// World space bounds of every instance of an object as `(min, max)`, or `None` if it
+    /// doesn't exist or has no instances.
+    pub fn object_bounds(&self, object_id: usize) -> Option<(cgmath::Point3<f32>, cgmath::Point3<f32>)> {
+        let aabb = self.objects.get(&object_id)?.world_aabb()?;
+        Some((aabb.min, aabb.max))
+    }
 
-        encoder.copy_buffer_to_buffer(
-            &staging_buffer,
-            0,
-            &self.gpu.uniform_buffer,
-            0,
-            std::mem::size_of::<Uniforms>() as wgpu::BufferAddress,
-        );
+    /// World space bounds of every instance of every world space object as `(min, max)`, or
+    /// `None` if nothing has instances. Useful with `Camera::frame_bounds`.
+    pub fn scene_bounds(&self) -> Option<(cgmath::Point3<f32>, cgmath::Point3<f32>)> {
+        let aabb = self
+            .objects
+            .values()
+            .filter(|object| !object.is_screen_space())
+            .filter_map(Object::world_aabb)
+            .fold(None, |bounds: Option<Aabb>, aabb| match bounds {
+                Some(bounds) => Some(bounds.union(&aabb)),
+                None => Some(aabb),
+            })?;
+        Some((aabb.min, aabb.max))
+    }
 
-        // We need to remember to submit our CommandEncoder's output
-        // otherwise we won't see any change.
-        self.gpu.queue.submit(&[encoder.finish()]);
+    /// The CPU side copies of an object's instances, including any not uploaded yet.
+    pub fn instances(&self, object_id: usize) -> Option<&[Instance]> {
+        self.objects.get(&object_id).map(Object::instances)
     }
 
-    pub fn render(&mut self) {
-        let frame = self
-            .gpu
-            .swap_chain
-            .get_next_texture()
-            .expect("Timeout getting texture");
+    pub fn instance_exists(&self, object_id: usize, instance_id: usize) -> bool {
+        self.objects
+            .get(&object_id)
+            .map_or(false, |object| instance_id < object.num_instances())
+    }
 
-        let mut encoder = self
-            .gpu
-            .device
-            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
-                label: Some("Render Encoder"),
-            });
+    /// Move an object to another render layer.
+    pub fn set_object_layer(&mut self, object_id: usize, layer: Layer) -> bool {
+        match self.objects.get_mut(&object_id) {
+            Some(object) => {
+                object.set_layer(layer);
+                true
+            }
+            None => false,
+        }
+    }
 
-        {
-            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                color_attachments: &[wgpu::RenderPassColorAttachmentDescriptor {
-                    attachment: &frame.view,
-                    resolve_target: None,
-                    load_op: wgpu::LoadOp::Clear,
-                    store_op: wgpu::StoreOp::Store,
-                    clear_color: self.config.clear_color,
-                }],
-                depth_stencil_attachment: None,
-            });
+    /// Draw only the instances of an object in `range`, or all of them with `None`.
+    pub fn set_instance_range(&mut self, object_id: usize, range: Option<std::ops::Range<usize>>) -> bool {
+        match self.objects.get_mut(&object_id) {
+            Some(object) => {
+                object.set_instance_range(range);
+                true
+            }
+            None => false,
+        }
+    }
 
-            render_pass.set_pipeline(&self.gpu.render_pipeline);
-            render_pass.set_bind_group(0, &self.gpu.uniform_bind_group, &[]);
+    /// Set where an object draws within its layer. Lower orders draw first, so later ones
+    /// cover them; objects with the same order draw in creation order.
+    pub fn set_object_order(&mut self, object_id: usize, order: i32) -> bool {
+        match self.objects.get_mut(&object_id) {
+            Some(object) => {
+                object.set_order(order);
+                true
+            }
+            None => false,
+        }
+    }
 
-            for object in &self.objects {
-                let num_instanaces = object.num_instances() as u32;
-                if num_instanaces > 0 {
-                    render_pass.set_vertex_buffer(0, object.vertex_buffer(), 0, 0);
-                    render_pass.set_index_buffer(object.index_buffer(), 0, 0);
-                    render_pass.draw_indexed(0..object.num_indices(), 0, 0..num_instanaces);                
+    /// Draw an object in screen space with the overlay camera, after every world space
+    /// object, or back in world space with the main camera.
+    pub fn set_object_screen_space(&mut self, object_id: usize, screen_space: bool) -> bool {
+        match self.objects.get_mut(&object_id) {
+            Some(object) => {
+                object.set_screen_space(screen_space);
+                if object.bind_group().is_some() {
+                    let bind_group = self.gpu.create_bind_group(object);
+                    object.set_bind_group(bind_group);
                 }
+                true
             }
+            None => false,
         }
+    }
 
-        self.gpu.queue.submit(&[encoder.finish()]);
+    /// Compile a custom vertex and fragment shader into a material objects can be drawn with.
+    ///
+    /// The shaders take the same vertex attributes and bind group as the built-in basic
+    /// shaders: the camera uniforms at binding 0, instances at 1, lights at 2 and the
+    /// object's material color at 3. Objects are still picked with their built-in shape.
+    pub fn create_material(&mut self, vs_source: &str, fs_source: &str) -> anyhow::Result<MaterialId> {
+        let index = self.gpu.materials.len();
+        let compiler = &mut self.gpu.compiler;
+
+        let vs_name = format!("material_{}.vert", index);
+        let fs_name = format!("material_{}.frag", index);
+        // Compiled shaders borrow the compiler, so each is turned into a module before the next
+        let vs_data = compiler.create_vertex_shader(vs_source, &vs_name, "main")?;
+        if cfg!(debug_assertions) {
+            shaders::check_layouts(&vs_name, &vs_data);
+        }
+        let vs_module = self.gpu.device.create_shader_module(vs_data);
+
+        let fs_data = compiler.create_fragment_shader(fs_source, &fs_name, "main")?;
+        if cfg!(debug_assertions) {
+            shaders::check_layouts(&fs_name, &fs_data);
+        }
+        let fs_module = self.gpu.device.create_shader_module(fs_data);
+
+        self.gpu.materials.push(Material { vs_module, fs_module });
+
+        Ok(MaterialId(index))
+    }
+
+    fn material_exists(&self, material: Option<MaterialId>) -> bool {
+        match material {
+            Some(MaterialId(index)) => index < self.gpu.materials.len(),
+            None => true,
+        }
+    }
+
+    /// Draw an object with a custom material's shaders, or the built-in ones with `None`.
+    /// Returns `false` if the object or the material doesn't exist.
+    pub fn set_object_material(&mut self, object_id: usize, material: Option<MaterialId>) -> bool {
+        if !self.material_exists(material) {
+            return false;
+        }
+
+        match self.objects.get_mut(&object_id) {
+            Some(object) => {
+                object.set_material(material);
+                self.gpu.ensure_pipeline(object.pipeline_config());
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Draw an object in a single `color` instead of its vertex colors, or go back to the
+    /// vertex colors with `None`. Lighting and instance colors still apply on top.
+    pub fn set_object_material_color(&mut self, object_id: usize, color: Option<Color>) -> bool {
+        match self.objects.get_mut(&object_id) {
+            Some(object) => {
+                object.set_material_color(&self.gpu.queue, color);
+                self.gpu.ensure_pipeline(object.pipeline_config());
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Limit how many instances an object can have, or remove the limit with `None`.
+    /// Creating instances past the limit fails instead of growing the instance buffer.
+    pub fn set_max_instances(&mut self, object_id: usize, max_instances: Option<usize>) -> bool {
+        match self.objects.get_mut(&object_id) {
+            Some(object) => {
+                object.set_max_instances(max_instances);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Remove every instance of an object. The object stays valid but stops drawing.
+    pub fn clear_instances(&mut self, object_id: usize) -> bool {
+        match self.objects.get_mut(&object_id) {
+            Some(object) => {
+                object.clear_instances();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Add an instance to an object, returning its id. Returns `None` if the object doesn't
+    /// exist or already has as many instances as it's allowed.
+    pub fn create_instance(&mut self, object_id: usize, position: cgmath::Vector3<f32>, rotation: cgmath::Quaternion<f32>) -> Option<usize> {
+        self.add_instance(object_id, Instance::new(position, rotation))
+    }
+
+    /// Create an instance tinted by an RGBA `color`, which is multiplied into the vertex colors.
+    pub fn create_instance_colored(&mut self, object_id: usize, position: cgmath::Vector3<f32>, rotation: cgmath::Quaternion<f32>, color: [f32; 4]) -> Option<usize> {
+        let instance = Instance {
+            color,
+            ..Instance::new(position, rotation)
+        };
+        self.add_instance(object_id, instance)
+    }
+
+    /// Create an instance rotated by yaw, pitch and roll angles, in the YXZ order described
+    /// by `euler_rotation`.
+    pub fn create_instance_euler(
+        &mut self,
+        object_id: usize,
+        position: cgmath::Vector3<f32>,
+        yaw: impl Into<cgmath::Rad<f32>>,
+        pitch: impl Into<cgmath::Rad<f32>>,
+        roll: impl Into<cgmath::Rad<f32>>,
+    ) -> Option<usize> {
+        self.create_instance(object_id, position, euler_rotation(yaw, pitch, roll))
+    }
+
+    /// Set an instance's rotation from yaw, pitch and roll angles, like
+    /// `create_instance_euler`. Its position, scale and color are kept.
+    pub fn update_instance_euler(
+        &mut self,
+        object_id: usize,
+        instance_id: usize,
+        yaw: impl Into<cgmath::Rad<f32>>,
+        pitch: impl Into<cgmath::Rad<f32>>,
+        roll: impl Into<cgmath::Rad<f32>>,
+    ) -> bool {
+        let rotation = euler_rotation(yaw, pitch, roll);
+        match self.objects.get_mut(&object_id) {
+            Some(object) => object.update_instance(instance_id, |instance| instance.rotation = rotation),
+            None => false,
+        }
+    }
+
+    fn add_instance(&mut self, object_id: usize, instance: Instance) -> Option<usize> {
+        let object = self.objects.get_mut(&object_id)?;
+        if object.add_instance(instance) {
+            Some(object.num_instances() - 1)
+        } else {
+            None
+        }
+    }
+
+    /// Copy every object's geometry, settings and instances, in creation order.
+    pub fn scene_snapshot(&self) -> SceneData {
+        let objects = self
+            .objects
+            .values()
+            .map(|object| ObjectData {
+                vertices: object.vertices().to_vec(),
+                indices: object.indices().map(<[u16]>::to_vec),
+                layer: object.layer(),
+                order: object.order(),
+                screen_space: object.is_screen_space(),
+                material_color: object.material_color(),
+                pipeline_config: object.pipeline_config(),
+                instances: object.instances().iter().map(InstanceData::from).collect(),
+            })
+            .collect();
+
+        SceneData { objects }
+    }
+
+    /// Replace every object with the ones in `scene`, returning their new ids in order.
+    pub fn load_scene(&mut self, scene: SceneData) -> Vec<usize> {
+        self.objects.clear();
+
+        scene
+            .objects
+            .into_iter()
+            .map(|data| {
+                let object_id = self.insert_object(&data.vertices, data.indices.as_deref(), data.pipeline_config);
+                self.set_object_layer(object_id, data.layer);
+                self.set_object_order(object_id, data.order);
+                self.set_object_screen_space(object_id, data.screen_space);
+                self.set_object_material_color(object_id, data.material_color);
+                for instance in data.instances {
+                    self.add_instance(object_id, instance.into());
+                }
+                object_id
+            })
+            .collect()
+    }
+
+    /// Resize the swap chain to `new_size` and update the camera's aspect ratio to match.
+    ///
+    /// Call this for both `Resized` and `ScaleFactorChanged` events, since moving the window
+    /// to a display with a different scale factor changes its size in physical pixels.
+    pub fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>) {
+        self.is_minimized = new_size.width == 0 || new_size.height == 0;
+        if self.is_minimized {
+            // Keep the old swap chain around until the window is restored
+            return;
+        }
+
+        self.size = new_size;
+        self.gpu.sc_desc.width = new_size.width;
+        self.gpu.sc_desc.height = new_size.height;
+        self.gpu.recreate_swap_chain();
+        self.gpu.recreate_multisampled_framebuffer();
+        self.camera.aspect = new_size.width as f32 / new_size.height as f32;
+    }
+
+    /// The device `State` renders with, for creating custom GPU resources.
+    pub fn device(&self) -> &wgpu::Device {
+        &self.gpu.device
+    }
+
+    pub fn queue(&self) -> &wgpu::Queue {
+        &self.gpu.queue
+    }
+
+    /// Name, vendor, backend and device type of the GPU in use.
+    pub fn adapter_info(&self) -> &wgpu::AdapterInfo {
+        &self.gpu.adapter_info
+    }
+
+    /// The `anisotropy_clamp` samplers should use, `None` if anisotropic filtering is disabled.
+    pub fn anisotropy_clamp(&self) -> Option<u8> {
+        self.gpu.anisotropy_clamp
+    }
+
+    /// Format of the swap chain and every pipeline's color target.
+    pub fn surface_format(&self) -> wgpu::TextureFormat {
+        self.gpu.sc_desc.format
+    }
+
+    pub fn is_minimized(&self) -> bool {
+        self.is_minimized
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Stop or resume all per-frame GPU work. While paused `update` and `render` return
+    /// immediately, so the camera, instances and uniforms aren't touched.
+    ///
+    /// Resizes still apply while paused, so the swap chain and camera aspect match the
+    /// window when rendering resumes. The pause isn't counted in the frame time.
+    pub fn set_paused(&mut self, paused: bool) {
+        if self.paused && !paused {
+            self.last_render = None;
+        }
+        self.paused = paused;
+    }
+
+    /// The present mode the swap chain was created with.
+    pub fn present_mode(&self) -> wgpu::PresentMode {
+        self.gpu.sc_desc.present_mode
+    }
+
+    /// Switch to the first supported mode in `preferences` (vsync) and recreate the swap chain.
+    pub fn set_present_mode(&mut self, preferences: &[wgpu::PresentMode]) {
+        let mode = select_present_mode(self.gpu.adapter_info.backend, preferences);
+        if self.gpu.sc_desc.present_mode != mode {
+            self.gpu.sc_desc.present_mode = mode;
+            self.gpu.recreate_swap_chain();
+        }
+    }
+
+    /// Run `callback` every frame after the scene is drawn, for example to draw a debug UI.
+    pub fn set_render_callback(&mut self, callback: impl FnMut(&mut RenderContext) + 'static) {
+        self.render_callback = Some(Box::new(callback));
+    }
+
+    pub fn clear_render_callback(&mut self) {
+        self.render_callback = None;
+    }
+
+    pub fn set_clear_color(&mut self, color: wgpu::Color) {
+        self.config.clear_color = color;
+    }
+
+    /// The lights world space objects are shaded with.
+    pub fn lights(&self) -> &[Light] {
+        &self.lights
+    }
+
+    /// Replace the lights world space objects are shaded with. Only the first
+    /// `MAX_LIGHTS` are used. Without any lights objects show their plain vertex colors,
+    /// and screen space objects are never lit.
+    pub fn set_lights(&mut self, lights: &[Light]) {
+        if lights.len() > light::MAX_LIGHTS {
+            log::warn!(
+                "{} lights set but only {} are supported, ignoring the rest",
+                lights.len(),
+                light::MAX_LIGHTS
+            );
+        }
+
+        self.lights = lights.iter().copied().take(light::MAX_LIGHTS).collect();
+        self.gpu.queue.write_buffer(
+            &self.gpu.light_buffer,
+            0,
+            bytemuck::cast_slice(&[LightUniforms::new(&self.lights)]),
+        );
+    }
+
+    /// Draw a world space line from `a` to `b` in the next frame only.
+    ///
+    /// Debug lines are drawn over every object, including screen space ones. They aren't
+    /// objects themselves, so they can't be picked or saved in scenes.
+    pub fn draw_line(&mut self, a: cgmath::Point3<f32>, b: cgmath::Point3<f32>, color: Color) {
+        for &point in &[a, b] {
+            self.debug_lines.push(Vertex::new(point.into(), [0.0, 0.0, 0.0], [0.0, 0.0], color));
+        }
+    }
+
+    /// Draw the edges of an axis aligned box in the next frame only, like `draw_line`.
+    pub fn draw_aabb(&mut self, min: cgmath::Point3<f32>, max: cgmath::Point3<f32>, color: Color) {
+        let corner = |i: usize| {
+            cgmath::Point3::new(
+                if i & 1 == 0 { min.x } else { max.x },
+                if i & 2 == 0 { min.y } else { max.y },
+                if i & 4 == 0 { min.z } else { max.z },
+            )
+        };
+
+        // Corners whose indices differ by one bit share an edge
+        for i in 0..8 {
+            for &bit in &[1, 2, 4] {
+                if i & bit == 0 {
+                    self.draw_line(corner(i), corner(i | bit), color);
+                }
+            }
+        }
+    }
+
+    /// The matrix the shaders transform world space positions by, including the
+    /// conversion to wgpu's clip space.
+    pub fn view_projection_matrix(&self) -> cgmath::Matrix4<f32> {
+        self.camera.build_view_projection_matrix()
+    }
+
+    /// The pixel of the window a world space point projects to, or `None` behind the camera.
+    pub fn world_to_screen(&self, point: cgmath::Point3<f32>) -> Option<Vector2f> {
+        self.camera.world_to_screen(point, self.size.width as f32, self.size.height as f32)
+    }
+
+    /// World space ray through a pixel of the window, for mouse picking.
+    pub fn screen_ray(&self, pixel: Vector2f) -> (cgmath::Point3<f32>, cgmath::Vector3<f32>) {
+        self.camera.screen_ray(&pixel, self.size.width as f32, self.size.height as f32)
+    }
+
+    /// The closest instance under a pixel as `(object_id, instance_id)`.
+    ///
+    /// Each instance is treated as a unit sphere around its position, scaled by its largest
+    /// scale component. Screen space objects aren't in the world, so they're never picked.
+    pub fn pick(&self, pixel: Vector2f) -> Option<(usize, usize)> {
+        use cgmath::EuclideanSpace;
+
+        let (origin, direction) = self.screen_ray(pixel);
+
+        let mut closest: Option<(f32, (usize, usize))> = None;
+        for (&object_id, object) in self.objects.iter().filter(|(_, object)| !object.is_screen_space()) {
+            for (instance_id, instance) in object.instances().iter().enumerate() {
+                let radius = instance.scale.x.max(instance.scale.y).max(instance.scale.z);
+                let center = cgmath::Point3::from_vec(instance.position);
+
+                if let Some(distance) = intersection::ray_sphere(origin, direction, center, radius) {
+                    if closest.map_or(true, |(closest_distance, _)| distance < closest_distance) {
+                        closest = Some((distance, (object_id, instance_id)));
+                    }
+                }
+            }
+        }
+
+        closest.map(|(_, ids)| ids)
+    }
+
+    /// Find the object and instance covering a pixel of the window by rendering their ids
+    /// and reading the pixel back, exact for any mesh.
+    ///
+    /// This renders the whole scene again and waits for the GPU, so it's meant for the
+    /// occasional click rather than every frame. Returns `None` outside the window or when
+    /// nothing covers the pixel.
+    pub fn pick_pixel(&self, x: u32, y: u32) -> Option<(usize, usize)> {
+        if self.is_minimized || x >= self.size.width || y >= self.size.height {
+            return None;
+        }
+
+        let texture = self.gpu.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("pick_texture"),
+            size: wgpu::Extent3d {
+                width: self.size.width,
+                height: self.size.height,
+                depth: 1,
+            },
+            array_layer_count: 1,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: PICK_FORMAT,
+            usage: wgpu::TextureUsage::OUTPUT_ATTACHMENT | wgpu::TextureUsage::COPY_SRC,
+        });
+        let view = texture.create_default_view();
+
+        // Copies must use whole rows aligned to 256 bytes, even for a single texel
+        let readback_size = COPY_BYTES_PER_ROW_ALIGNMENT;
+        let readback_buffer = self.gpu.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("pick_readback_buffer"),
+            size: readback_size as wgpu::BufferAddress,
+            usage: wgpu::BufferUsage::MAP_READ | wgpu::BufferUsage::COPY_DST,
+        });
+
+        let mut encoder = self
+            .gpu
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Pick Encoder"),
+            });
+
+        // Zero ids mean nothing was drawn
+        let clear_ids = wgpu::Color::TRANSPARENT;
+        self.encode_passes(&mut encoder, &view, None, &self.gpu.pick_pipelines, clear_ids, &mut FrameStats::default());
+
+        encoder.copy_texture_to_buffer(
+            wgpu::TextureCopyView {
+                texture: &texture,
+                mip_level: 0,
+                array_layer: 0,
+                origin: wgpu::Origin3d { x, y, z: 0 },
+            },
+            wgpu::BufferCopyView {
+                buffer: &readback_buffer,
+                offset: 0,
+                bytes_per_row: readback_size,
+                rows_per_image: 1,
+            },
+            wgpu::Extent3d {
+                width: 1,
+                height: 1,
+                depth: 1,
+            },
+        );
+
+        self.gpu.queue.submit(&[encoder.finish()]);
+
+        let mapping = readback_buffer.map_read(0, readback_size as wgpu::BufferAddress);
+        self.gpu.device.poll(wgpu::Maintain::Wait);
+        let mapping = match futures::executor::block_on(mapping) {
+            Ok(mapping) => mapping,
+            Err(e) => {
+                log::error!("Failed to read back the picked pixel: {:?}", e);
+                return None;
+            }
+        };
+
+        // The buffer is unmapped when the mapping is dropped
+        let texel: &[u32] = bytemuck::cast_slice(&mapping.as_slice()[..8]);
+        let ids = [texel[0], texel[1]];
+
+        match ids {
+            [0, _] => None,
+            [object_id, instance_id] => Some((object_id as usize - 1, instance_id as usize)),
+        }
+    }
+
+    pub fn camera(&self) -> &Camera {
+        &self.camera
+    }
+
+    pub fn camera_mut(&mut self) -> &mut Camera {
+        &mut self.camera
+    }
+
+    /// The camera screen space objects are drawn with.
+    pub fn overlay_camera(&self) -> &OverlayCamera {
+        &self.overlay_camera
+    }
+
+    pub fn overlay_camera_mut(&mut self) -> &mut OverlayCamera {
+        &mut self.overlay_camera
+    }
+
+    pub fn camera_controller_mut(&mut self) -> &mut CameraController {
+        &mut self.camera_controller
+    }
+
+    pub fn input(&mut self, event: &WindowEvent) -> bool {
+        self.camera_controller.process_events(event)
+    }
+
+    /// Route a winit event to the right method, returning how the event loop should proceed.
+    ///
+    /// Window events go to `input` first. Resizes, including the new size that comes with a
+    /// HiDPI `ScaleFactorChanged`, go to `resize`, `RedrawRequested` runs `update` and
+    /// `render`, and once all other events are handled a redraw of `window` is requested and
+    /// a captured cursor is re-centered.
+    /// Closing the window or a failed render exits.
+    pub fn handle_event(&mut self, event: &Event<()>, window: &Window) -> ControlFlow {
+        match event {
+            Event::WindowEvent {
+                event,
+                window_id,
+            } if *window_id == window.id() => {
+                if self.input(event) {
+                    return ControlFlow::Poll;
+                }
+                match event {
+                    WindowEvent::CloseRequested => return ControlFlow::Exit,
+                    WindowEvent::Resized(physical_size) => self.resize(*physical_size),
+                    WindowEvent::ScaleFactorChanged { new_inner_size, .. } => self.resize(**new_inner_size),
+                    _ => {}
+                }
+            }
+            Event::RedrawRequested(window_id) if *window_id == window.id() => {
+                self.update();
+                if let Err(e) = self.render() {
+                    log::error!("State::render() failed: {}", e);
+                    return ControlFlow::Exit;
+                }
+            }
+            Event::MainEventsCleared => {
+                // Where the cursor can't be moved, grabbing it alone still keeps it in the window
+                let _ = self.camera_controller.recenter_cursor(window);
+                window.request_redraw();
+            }
+            _ => {}
+        }
+
+        ControlFlow::Poll
+    }
+
+    pub fn update(&mut self) {
+        if self.paused {
+            return;
+        }
+
+        let follow_position = self.camera_controller.follow_target().and_then(|(object_id, instance_id)| {
+            use cgmath::EuclideanSpace;
+
+            let instance = self.objects.get(&object_id)?.instances().get(instance_id)?;
+            Some(cgmath::Point3::from_vec(instance.position))
+        });
+        self.camera_controller.update_camera_following(&mut self.camera, follow_position);
+        self.uniforms.update_view_proj(&self.camera);
+
+        // Instances are only uploaded once per frame, however many were added
+        for (&object_id, object) in self.objects.iter_mut() {
+            if object.upload_instances(&self.gpu.device, &self.gpu.queue, object_id) {
+                let bind_group = self.gpu.create_bind_group(object);
+                object.set_bind_group(bind_group);
+            }
+        }
+
+        self.gpu.queue.write_buffer(&self.gpu.uniform_buffer, 0, bytemuck::cast_slice(&[self.uniforms]));
+
+        let overlay_view_proj = self
+            .overlay_camera
+            .build_projection_matrix(self.size.width as f32, self.size.height as f32);
+        self.overlay_uniforms.set_view_proj(overlay_view_proj);
+        self.gpu.queue.write_buffer(&self.gpu.overlay_uniform_buffer, 0, bytemuck::cast_slice(&[self.overlay_uniforms]));
+    }
+
+    /// Number of instances that survived frustum culling in the last frame.
+    pub fn last_drawn_instances(&self) -> usize {
+        self.last_frame_stats.instances_drawn
+    }
+
+    /// Statistics from the last frame, as returned by `render`.
+    pub fn last_frame_stats(&self) -> FrameStats {
+        self.last_frame_stats
+    }
+
+    /// Time between the two most recent calls to `render`.
+    pub fn last_frame_time(&self) -> Duration {
+        self.frame_times.back().copied().unwrap_or_default()
+    }
+
+    /// Frames per second, averaged over the last `FPS_SAMPLE_COUNT` frames.
+    pub fn fps(&self) -> f32 {
+        let total: Duration = self.frame_times.iter().sum();
+        if total.as_secs_f32() > 0.0 {
+            self.frame_times.len() as f32 / total.as_secs_f32()
+        } else {
+            0.0
+        }
+    }
+
+    fn record_frame_time(&mut self) {
+        let now = Instant::now();
+        if let Some(last_render) = self.last_render {
+            if self.frame_times.len() == FPS_SAMPLE_COUNT {
+                self.frame_times.pop_front();
+            }
+            self.frame_times.push_back(now - last_render);
+        }
+        self.last_render = Some(now);
+    }
+
+    /// Render a frame, returning what was drawn.
+    ///
+    /// If the swap chain can't provide a frame it's recreated and the frame is skipped, since
+    /// wgpu reports an outdated or lost swap chain the same way as a timeout. Nothing is rendered while minimized or paused.
+    /// Skipped frames are reported as empty. Fails for headless states, which have no
+    /// swap chain.
+    pub fn render(&mut self) -> anyhow::Result<FrameStats> {
+        anyhow::ensure!(!self.is_headless(), "headless states can't render to a window, use render_to_rgba");
+
+        // Debug lines only last one frame, even if it's skipped
+        let debug_lines = std::mem::take(&mut self.debug_lines);
+
+        if self.is_minimized || self.paused {
+            return Ok(FrameStats::default());
+        }
+
+        self.record_frame_time();
+        let mut stats = FrameStats {
+            frame_time: self.last_frame_time(),
+            ..FrameStats::default()
+        };
+
+        let swap_chain = self.gpu.swap_chain.as_mut().expect("windowed state without a swap chain");
+        let frame = match swap_chain.get_next_texture() {
+            Ok(frame) => frame,
+            Err(wgpu::TimeOut) => {
+                self.gpu.recreate_swap_chain();
+                return Ok(stats);
+            }
+        };
+
+        let mut encoder = self
+            .gpu
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Render Encoder"),
+            });
+
+        self.encode_passes(
+            &mut encoder,
+            &frame.view,
+            self.gpu.multisampled_view(),
+            &self.gpu.pipelines,
+            self.config.clear_color,
+            &mut stats,
+        );
+        if self.upload_debug_lines(&debug_lines) {
+            self.draw_debug_lines(&mut encoder, &frame.view, &mut stats);
+        }
+        self.last_frame_stats = stats;
+
+        if let Some(callback) = self.render_callback.as_mut() {
+            callback(&mut RenderContext {
+                device: &self.gpu.device,
+                queue: &self.gpu.queue,
+                encoder: &mut encoder,
+                view: &frame.view,
+                format: self.gpu.sc_desc.format,
+                size: self.size,
+            });
+        }
+
+        self.gpu.queue.submit(&[encoder.finish()]);
+
+        Ok(stats)
+    }
+
+    /// Render `frames` frames into an offscreen texture the size of the window and time
+    /// them, for tracking performance across changes. Use a `new_headless` state to
+    /// benchmark without a window.
+    ///
+    /// Each frame runs `update`, draws every object and waits for the GPU to finish, so
+    /// the times cover the whole frame and aren't limited by vsync. Nothing is presented,
+    /// and debug lines and the render callback are skipped. Returns all zeros while
+    /// minimized.
+    pub fn bench(&mut self, frames: usize) -> BenchResult {
+        if self.is_minimized {
+            return BenchResult::default();
+        }
+
+        let texture = self.gpu.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("bench_texture"),
+            size: wgpu::Extent3d {
+                width: self.size.width,
+                height: self.size.height,
+                depth: 1,
+            },
+            array_layer_count: 1,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: self.gpu.sc_desc.format,
+            usage: wgpu::TextureUsage::OUTPUT_ATTACHMENT,
+        });
+        let view = texture.create_default_view();
+
+        let mut frame_times = Vec::with_capacity(frames);
+        for _ in 0..frames {
+            let start = Instant::now();
+            self.update();
+
+            let mut encoder = self
+                .gpu
+                .device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("Bench Encoder"),
+                });
+            self.encode_passes(
+                &mut encoder,
+                &view,
+                self.gpu.multisampled_view(),
+                &self.gpu.pipelines,
+                self.config.clear_color,
+                &mut FrameStats::default(),
+            );
+            self.gpu.queue.submit(&[encoder.finish()]);
+            self.gpu.device.poll(wgpu::Maintain::Wait);
+
+            frame_times.push(start.elapsed());
+        }
+
+        BenchResult::from_frame_times(&frame_times)
+    }
+
+    /// Render the scene into an offscreen texture the size of the window and read it back
+    /// as RGBA, four bytes per pixel in rows from the top left corner. Works on headless
+    /// states, for screenshots and rendering tests.
+    ///
+    /// Like `bench` this runs `update` first, skips debug lines and the render callback,
+    /// and waits for the GPU. sRGB surface formats give sRGB encoded bytes.
+    pub fn render_to_rgba(&mut self) -> anyhow::Result<Vec<u8>> {
+        anyhow::ensure!(!self.is_minimized, "can't render a minimized window");
+
+        self.update();
+
+        let texture = self.gpu.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("capture_texture"),
+            size: wgpu::Extent3d {
+                width: self.size.width,
+                height: self.size.height,
+                depth: 1,
+            },
+            array_layer_count: 1,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: self.gpu.sc_desc.format,
+            usage: wgpu::TextureUsage::OUTPUT_ATTACHMENT | wgpu::TextureUsage::COPY_SRC,
+        });
+        let view = texture.create_default_view();
+
+        // Rows are padded to the copy alignment in the buffer and unpadded when read back
+        let row_size = self.size.width * 4;
+        let padded_row_size =
+            (row_size + COPY_BYTES_PER_ROW_ALIGNMENT - 1) / COPY_BYTES_PER_ROW_ALIGNMENT * COPY_BYTES_PER_ROW_ALIGNMENT;
+        let readback_size = (padded_row_size * self.size.height) as wgpu::BufferAddress;
+        let readback_buffer = self.gpu.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("capture_readback_buffer"),
+            size: readback_size,
+            usage: wgpu::BufferUsage::MAP_READ | wgpu::BufferUsage::COPY_DST,
+        });
+
+        let mut encoder = self
+            .gpu
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Capture Encoder"),
+            });
+        self.encode_passes(
+            &mut encoder,
+            &view,
+            self.gpu.multisampled_view(),
+            &self.gpu.pipelines,
+            self.config.clear_color,
+            &mut FrameStats::default(),
+        );
+
+        encoder.copy_texture_to_buffer(
+            wgpu::TextureCopyView {
+                texture: &texture,
+                mip_level: 0,
+                array_layer: 0,
+                origin: wgpu::Origin3d::ZERO,
+            },
+            wgpu::BufferCopyView {
+                buffer: &readback_buffer,
+                offset: 0,
+                bytes_per_row: padded_row_size,
+                rows_per_image: self.size.height,
+            },
+            wgpu::Extent3d {
+                width: self.size.width,
+                height: self.size.height,
+                depth: 1,
+            },
+        );
+
+        self.gpu.queue.submit(&[encoder.finish()]);
+
+        let mapping = readback_buffer.map_read(0, readback_size);
+        self.gpu.device.poll(wgpu::Maintain::Wait);
+        let mapping = futures::executor::block_on(mapping)
+            .map_err(|e| anyhow::anyhow!("Failed to read back the rendered frame: {:?}", e))?;
+
+        let mut pixels = Vec::with_capacity((row_size * self.size.height) as usize);
+        for row in mapping.as_slice().chunks(padded_row_size as usize) {
+            pixels.extend_from_slice(&row[..row_size as usize]);
+        }
+
+        match self.gpu.sc_desc.format {
+            wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb => {
+                for pixel in pixels.chunks_exact_mut(4) {
+                    pixel.swap(0, 2);
+                }
+            }
+            _ => {}
+        }
+
+        Ok(pixels)
+    }
+
+    /// Copy a frame's debug lines to the GPU, returning whether there were any to draw.
+    fn upload_debug_lines(&mut self, debug_lines: &[Vertex]) -> bool {
+        if debug_lines.is_empty() {
+            return false;
+        }
+
+        match self.debug_line_object.as_mut() {
+            Some(object) => object.set_geometry(&self.gpu.device, debug_lines, None),
+            None => {
+                let config = PipelineConfig::lines();
+                self.gpu.ensure_pipeline(config);
+
+                let mut object = Object::new(&self.gpu.device, debug_lines, None, config);
+                object.add_instance(Instance::new(cgmath::Vector3::new(0.0, 0.0, 0.0), cgmath::One::one()));
+                // Debug lines are never drawn into the pick texture, so the id doesn't matter
+                object.upload_instances(&self.gpu.device, &self.gpu.queue, 0);
+                let bind_group = self.gpu.create_bind_group(&object);
+                object.set_bind_group(bind_group);
+                self.debug_line_object = Some(object);
+            }
+        }
+
+        true
+    }
+
+    /// Draw the uploaded debug lines over everything in `view` so far.
+    fn draw_debug_lines(&self, encoder: &mut wgpu::CommandEncoder, view: &wgpu::TextureView, stats: &mut FrameStats) {
+        let object = match self.debug_line_object.as_ref() {
+            Some(object) => object,
+            None => return,
+        };
+        let bind_group = match object.bind_group() {
+            Some(bind_group) => bind_group,
+            None => return,
+        };
+
+        let mut render_pass = begin_render_pass(encoder, view, self.gpu.multisampled_view(), false, self.config.clear_color);
+        render_pass.set_pipeline(&self.gpu.pipelines[&object.pipeline_config()]);
+        render_pass.set_bind_group(0, bind_group, &[]);
+        render_pass.set_vertex_buffer(0, object.vertex_buffer(), 0, 0);
+        render_pass.draw(0..object.num_vertices(), 0..1);
+        stats.draw_calls += 1;
+    }
+
+    /// Record the passes drawing every object into `view`: one per layer for world space
+    /// objects, then one more for screen space objects. Only the first pass clears.
+    ///
+    /// With a `multisampled` view the passes draw into it and resolve into `view`; it has
+    /// to match the sample count `pipelines` were created with.
+    fn encode_passes(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        view: &wgpu::TextureView,
+        multisampled: Option<&wgpu::TextureView>,
+        pipelines: &HashMap<PipelineConfig, wgpu::RenderPipeline>,
+        clear_color: wgpu::Color,
+        stats: &mut FrameStats,
+    ) {
+        let frustum = Frustum::from_matrix(&self.camera.build_view_projection_matrix());
+        let layers = self.layers();
+
+        for (index, &layer) in layers.iter().enumerate() {
+            // Later layers draw over the earlier ones
+            let mut render_pass = begin_render_pass(encoder, view, multisampled, index == 0, clear_color);
+            render_pass.set_bind_group(0, &self.gpu.uniform_bind_group, &[]);
+
+            self.draw_layer(&mut render_pass, pipelines, layer, false, &frustum, stats);
+        }
+
+        // Screen space objects go over the whole scene, still ordered by layer
+        if self.objects.values().any(Object::is_screen_space) {
+            let overlay_frustum = Frustum::from_matrix(
+                &self
+                    .overlay_camera
+                    .build_projection_matrix(self.size.width as f32, self.size.height as f32),
+            );
+
+            let mut render_pass = begin_render_pass(encoder, view, multisampled, false, clear_color);
+            render_pass.set_bind_group(0, &self.gpu.uniform_bind_group, &[]);
+
+            for &layer in &layers {
+                self.draw_layer(&mut render_pass, pipelines, layer, true, &overlay_frustum, stats);
+            }
+        }
+    }
+
+    /// Every layer in use, lowest first. The frame still has to be cleared when there's
+    /// nothing to draw, so this is never empty.
+    fn layers(&self) -> Vec<Layer> {
+        let mut layers = self.objects.values().map(Object::layer).collect::<Vec<_>>();
+        layers.sort();
+        layers.dedup();
+
+        if layers.is_empty() {
+            layers.push(Layer::default());
+        }
+        layers
+    }
+
+    /// Draw the visible instances of every object in `layer` that is or isn't in screen
+    /// space, adding what was drawn to `stats`.
+    fn draw_layer<'a>(
+        &'a self,
+        render_pass: &mut wgpu::RenderPass<'a>,
+        pipelines: &'a HashMap<PipelineConfig, wgpu::RenderPipeline>,
+        layer: Layer,
+        screen_space: bool,
+        frustum: &Frustum,
+        stats: &mut FrameStats,
+    ) {
+        let mut objects = self
+            .objects
+            .values()
+            .filter(|object| object.layer() == layer && object.is_screen_space() == screen_space)
+            .collect::<Vec<_>>();
+        // Stable, so objects with the same order still draw in creation order
+        objects.sort_by_key(|object| object.order());
+
+        for object in objects {
+            let num_instanaces = object.num_instances() as u32;
+            // Objects are skipped until they have instances uploaded with their own bind group
+            match object.bind_group() {
+                Some(bind_group) if num_instanaces > 0 => {
+                    render_pass.set_pipeline(&pipelines[&object.pipeline_config()]);
+                    render_pass.set_bind_group(0, bind_group, &[]);
+                    render_pass.set_vertex_buffer(0, object.vertex_buffer(), 0, 0);
+                    if let Some(index_buffer) = object.index_buffer() {
+                        render_pass.set_index_buffer(index_buffer, 0, 0);
+                    }
+
+                    for range in object.visible_instance_ranges(frustum) {
+                        stats.draw_calls += 1;
+                        stats.instances_drawn += range.len();
+                        stats.triangles += range.len() * object.num_triangles() as usize;
+                        let instances = range.start as u32..range.end as u32;
+                        if object.index_buffer().is_some() {
+                            render_pass.draw_indexed(0..object.num_indices(), 0, instances);
+                        } else {
+                            render_pass.draw(0..object.num_vertices(), instances);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+impl Drop for State {
+    /// Let submitted work finish before the fields, and the GPU resources they own, drop.
+    fn drop(&mut self) {
+        self.gpu.device.poll(wgpu::Maintain::Wait);
+    }
+}
+
+/// Begin a pass drawing into `view`, either clearing it to `clear_color` or drawing over it.
+/// With a `multisampled` view the pass draws into that instead and resolves into `view`.
+fn begin_render_pass<'a>(
+    encoder: &'a mut wgpu::CommandEncoder,
+    view: &'a wgpu::TextureView,
+    multisampled: Option<&'a wgpu::TextureView>,
+    clear: bool,
+    clear_color: wgpu::Color,
+) -> wgpu::RenderPass<'a> {
+    let load_op = if clear {
+        wgpu::LoadOp::Clear
+    } else {
+        wgpu::LoadOp::Load
+    };
+
+    let (attachment, resolve_target) = match multisampled {
+        Some(multisampled) => (multisampled, Some(view)),
+        None => (view, None),
+    };
+
+    encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        color_attachments: &[wgpu::RenderPassColorAttachmentDescriptor {
+            attachment,
+            resolve_target,
+            load_op,
+            store_op: wgpu::StoreOp::Store,
+            clear_color,
+        }],
+        depth_stencil_attachment: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graphics::color;
+
+    const SIZE: u32 = 64;
+
+    /// Render a `SIZE` pixel square headless state set up by `setup`, or `None` on machines
+    /// without a GPU adapter to render with.
+    fn render_to_rgba(setup: impl FnOnce(&mut State)) -> Option<Vec<u8>> {
+        let adapter = futures::executor::block_on(wgpu::Adapter::request(
+            &wgpu::RequestAdapterOptions {
+                power_preference: wgpu::PowerPreference::Default,
+                compatible_surface: None,
+            },
+            wgpu::BackendBit::PRIMARY,
+        ));
+        if adapter.is_none() {
+            eprintln!("No GPU adapter available, skipping rendering test");
+            return None;
+        }
+
+        let config = GraphicsConfig {
+            surface_format: wgpu::TextureFormat::Rgba8Unorm,
+            ..GraphicsConfig::default()
+        };
+        let size = winit::dpi::PhysicalSize::new(SIZE, SIZE);
+        let mut state = futures::executor::block_on(State::new_headless(size, config)).unwrap();
+        setup(&mut state);
+        Some(state.render_to_rgba().unwrap())
+    }
+
+    /// Compare pixels with their golden values, allowing each channel to be off by
+    /// `tolerance` for differences in rasterization between GPUs.
+    fn assert_pixels(pixels: &[u8], golden: &[((u32, u32), [u8; 4])], tolerance: u8) {
+        for &((x, y), expected) in golden {
+            let start = ((y * SIZE + x) * 4) as usize;
+            let actual = &pixels[start..start + 4];
+            let matches = actual
+                .iter()
+                .zip(&expected)
+                .all(|(&actual, &expected)| (actual as i16 - expected as i16).abs() <= tolerance as i16);
+            assert!(
+                matches,
+                "pixel ({}, {}) is {:?}, expected {:?} within {}",
+                x,
+                y,
+                actual,
+                expected,
+                tolerance
+            );
+        }
+    }
+
+    #[test]
+    fn draws_a_triangle() {
+        let pixels = render_to_rgba(|state| {
+            let vertices = [
+                Vertex::new_2d(-1.0, -1.0, color::RED),
+                Vertex::new_2d(1.0, -1.0, color::RED),
+                Vertex::new_2d(0.0, 1.0, color::RED),
+            ];
+            let object_id = state.create_object(&vertices, &[0, 1, 2]);
+            state.create_instance(object_id, cgmath::Vector3::new(0.0, 0.0, 0.0), cgmath::One::one());
+
+            let camera = state.camera_mut();
+            camera.eye = cgmath::Point3::new(0.0, 0.0, 5.0);
+            camera.target = cgmath::Point3::new(0.0, 0.0, 0.0);
+        });
+        let pixels = match pixels {
+            Some(pixels) => pixels,
+            None => return,
+        };
+
+        assert_eq!(pixels.len(), (SIZE * SIZE * 4) as usize);
+        let red = [255, 0, 0, 255];
+        let clear = [0, 0, 0, 255];
+        assert_pixels(
+            &pixels,
+            &[
+                ((SIZE / 2, SIZE / 2), red),
+                // The wide bottom of the triangle is red, the same spot mirrored across the
+                // middle isn't, so the image isn't upside down
+                ((44, 46), red),
+                ((44, 17), clear),
+                ((0, 0), clear),
+                ((SIZE - 1, SIZE - 1), clear),
+            ],
+            2,
+        );
     }
 }
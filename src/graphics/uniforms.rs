@@ -1,23 +1,28 @@
 use crate::graphics::Camera;
 
 #[repr(C)]
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct Uniforms {
-    view_proj: cgmath::Matrix4<f32>,
+    // Stored as plain arrays since cgmath's matrices aren't Pod
+    view_proj: [[f32; 4]; 4],
 }
 
-unsafe impl bytemuck::Pod for Uniforms {}
-unsafe impl bytemuck::Zeroable for Uniforms {}
+// Must match the uniform block in basic.vert
+const _: () = assert!(std::mem::size_of::<Uniforms>() == 64);
 
 impl Uniforms {
     pub fn new() -> Self {
         use cgmath::SquareMatrix;
         Self {
-            view_proj: cgmath::Matrix4::identity(),
+            view_proj: cgmath::Matrix4::identity().into(),
         }
     }
 
     pub fn update_view_proj(&mut self, camera: &Camera) {
-        self.view_proj = camera.build_view_projection_matrix();
+        self.view_proj = camera.build_view_projection_matrix().into();
+    }
+
+    pub fn set_view_proj(&mut self, view_proj: cgmath::Matrix4<f32>) {
+        self.view_proj = view_proj.into();
     }
 }
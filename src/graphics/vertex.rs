@@ -1,20 +1,36 @@
+use serde::{Deserialize, Serialize};
+
 use crate::graphics::Color;
 
 #[repr(C)]
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable, Serialize, Deserialize)]
 pub struct Vertex {
     pub position: [f32; 3],
     pub color: Color,
+    pub normal: [f32; 3],
+    pub tex_coords: [f32; 2],
 }
 
-unsafe impl bytemuck::Pod for Vertex {}
-unsafe impl bytemuck::Zeroable for Vertex {}
+// Catches fields added without updating `descriptor` and the vertex shader inputs
+const _: () = assert!(std::mem::size_of::<Vertex>() == 44);
 
 impl Vertex {
+    pub fn new(position: [f32; 3], normal: [f32; 3], tex_coords: [f32; 2], color: Color) -> Self {
+        Self {
+            position,
+            color,
+            normal,
+            tex_coords,
+        }
+    }
+
+    /// A vertex on the XY plane, facing +Z.
     pub fn new_2d(x: f32, y: f32, color: Color) -> Self {
         Self {
             position: [x, y, 0.0],
             color,
+            normal: [0.0, 0.0, 1.0],
+            tex_coords: [0.0, 0.0],
         }
     }
 
@@ -34,6 +50,16 @@ impl Vertex {
                     shader_location: 1,
                     format: wgpu::VertexFormat::Float3,
                 },
+                wgpu::VertexAttributeDescriptor {
+                    offset: mem::size_of::<[f32; 6]>() as wgpu::BufferAddress,
+                    shader_location: 2,
+                    format: wgpu::VertexFormat::Float3,
+                },
+                wgpu::VertexAttributeDescriptor {
+                    offset: mem::size_of::<[f32; 9]>() as wgpu::BufferAddress,
+                    shader_location: 3,
+                    format: wgpu::VertexFormat::Float2,
+                },
             ],
         }
     }